@@ -1,3 +1,13 @@
 fn main() {
+    // The CLI looks for the app's settings under the bundle identifier, so
+    // take it from the same config Tauri builds the app from.
+    println!("cargo:rerun-if-changed=tauri.conf.json");
+    let conf = std::fs::read_to_string("tauri.conf.json").expect("read tauri.conf.json");
+    let conf: serde_json::Value = serde_json::from_str(&conf).expect("parse tauri.conf.json");
+    let identifier = conf["identifier"]
+        .as_str()
+        .expect("tauri.conf.json has an identifier");
+    println!("cargo:rustc-env=APP_IDENTIFIER={identifier}");
+
     tauri_build::build()
 }
@@ -0,0 +1,149 @@
+//! Headless entry points for scripted use (e.g. MDM inventory collection).
+//!
+//! ```text
+//! app-uninstaller export-inventory [--format json|csv|md] [--output PATH] [--related]
+//! ```
+//!
+//! Without `--output` the inventory is written to stdout.
+
+use anyhow::{Context, Result, bail};
+use std::{io::Write, path::PathBuf};
+
+use crate::core;
+use crate::models::{ExportFormat, Settings};
+
+/// Bundle identifier from `tauri.conf.json`, set by `build.rs`. Tauri
+/// resolves the app config directory to
+/// `~/Library/Application Support/<identifier>` on macOS; the CLI reads the
+/// same settings and exclusion list from there.
+const APP_IDENTIFIER: &str = env!("APP_IDENTIFIER");
+
+fn config_file(name: &str) -> Option<PathBuf> {
+    home::home_dir().map(|home| {
+        home.join("Library")
+            .join("Application Support")
+            .join(APP_IDENTIFIER)
+            .join(name)
+    })
+}
+
+fn user_exclusions() -> Result<core::Exclusions> {
+    match config_file(core::EXCLUSIONS_FILE) {
+        Some(path) => core::load_user_exclusions(&path),
+        None => Ok(core::Exclusions::default()),
+    }
+}
+
+fn user_settings() -> Result<Settings> {
+    match config_file(core::SETTINGS_FILE) {
+        Some(path) => core::load_settings(&path),
+        None => Ok(Settings::default()),
+    }
+}
+
+const USAGE: &str =
+    "usage: app-uninstaller export-inventory [--format json|csv|md] [--output PATH] [--related]";
+
+pub fn parse_format(s: &str) -> Result<ExportFormat> {
+    Ok(match s.to_ascii_lowercase().as_str() {
+        "json" => ExportFormat::Json,
+        "csv" => ExportFormat::Csv,
+        "md" | "markdown" => ExportFormat::Markdown,
+        other => bail!("Unknown format {other:?}; expected json, csv or md"),
+    })
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExportArgs {
+    pub format: ExportFormat,
+    pub output: Option<PathBuf>,
+    pub include_related: bool,
+}
+
+pub fn parse_export_args<I: IntoIterator<Item = String>>(args: I) -> Result<ExportArgs> {
+    let mut parsed = ExportArgs {
+        format: ExportFormat::Json,
+        output: None,
+        include_related: false,
+    };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" | "-f" => {
+                parsed.format = parse_format(&args.next().context(USAGE)?)?;
+            }
+            "--output" | "-o" => {
+                parsed.output = Some(PathBuf::from(args.next().context(USAGE)?));
+            }
+            "--related" => parsed.include_related = true,
+            other => bail!("Unexpected argument {other:?}\n{USAGE}"),
+        }
+    }
+    Ok(parsed)
+}
+
+/// Handle a CLI subcommand. Returns `None` when `args` (without the program
+/// name) don't name one, so the caller should start the GUI instead.
+pub fn run(args: &[String]) -> Option<Result<()>> {
+    match args.first().map(String::as_str) {
+        Some("export-inventory") => Some(export(args[1..].to_vec())),
+        _ => None,
+    }
+}
+
+fn export(args: Vec<String>) -> Result<()> {
+    let args = parse_export_args(args)?;
+    let settings = user_settings()?;
+    let exclusions = user_exclusions()?;
+    let sink = core::TerminalSink::default();
+    match args.output {
+        Some(path) => {
+            let n = core::export_inventory(
+                args.format,
+                &path,
+                args.include_related,
+                &settings,
+                &exclusions,
+                &sink,
+            )?;
+            eprintln!("Wrote {n} app(s) to {}", path.display());
+        }
+        None => {
+            let apps = core::run_scan(&settings.user_scan_roots(), &sink)?;
+            let entries = core::build_inventory(
+                &apps,
+                args.include_related,
+                settings.related_max_depth,
+                &exclusions,
+            );
+            let rendered = core::render_inventory(&entries, args.format)?;
+            std::io::stdout().write_all(rendered.as_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &[&str]) -> Vec<String> {
+        s.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_export_flags() {
+        let parsed = parse_export_args(args(&["--format", "md", "-o", "/tmp/x.md", "--related"]));
+        assert_eq!(
+            parsed.unwrap(),
+            ExportArgs {
+                format: ExportFormat::Markdown,
+                output: Some(PathBuf::from("/tmp/x.md")),
+                include_related: true,
+            }
+        );
+        assert!(parse_export_args(args(&["--format", "xml"])).is_err());
+        assert!(parse_export_args(args(&["--output"])).is_err());
+        assert!(run(&args(&["--some-gui-flag"])).is_none());
+    }
+}
@@ -1,105 +1,149 @@
 //! Tauri command handlers.
 //!
-//! Each command runs the corresponding `core` function on a blocking task
-//! and emits typed `progress` events while it runs.
+//! Each command runs the corresponding `core` function on a blocking task,
+//! reporting progress through a [`TauriSink`].
 
-use std::path::PathBuf;
-use tauri::AppHandle;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
 
-use crate::core;
-use crate::models::{AppInfo, UninstallFailure, UninstallReport};
-use crate::progress::{self, ProgressEvent};
+use crate::core::{self, CoreError};
+use crate::models::{
+    AccessReport, AppInfo, AppProtection, AppSecurityInfo, Architecture, CleanCategory, CleanItem,
+    CleanReport, DuplicateGroup, ExclusionRules, ExportFormat, FailureKind, InstallSource,
+    MigrationMove, MigrationReport, ProfileAppResult, ProfileReport, ResetReport, RestoreReport,
+    Settings, StorageReport, ThinReport, UninstallFailure, UninstallReport, UpdateCheck,
+};
+use crate::progress::TauriSink;
 
 #[tauri::command]
-pub async fn list_apps(app: AppHandle) -> Result<Vec<AppInfo>, String> {
-    progress::emit(
-        &app,
-        ProgressEvent::RefreshApps {
-            progress: 0.0,
-            message: "Scanning /Applications and ~/Applications...".into(),
-            finished: false,
-            error: None,
-        },
-    );
+pub async fn list_apps(
+    app: AppHandle,
+    source: Option<InstallSource>,
+) -> Result<Vec<AppInfo>, CoreError> {
+    let roots = user_settings(&app)?.user_scan_roots();
+    let sink = TauriSink::new(&app);
+    let apps =
+        tauri::async_runtime::spawn_blocking(move || core::run_scan(&roots, &sink)).await??;
 
-    let app_for_progress = app.clone();
-    let result = tauri::async_runtime::spawn_blocking(move || {
-        core::scan_apps_with_progress(|p, msg| {
-            progress::emit(
-                &app_for_progress,
-                ProgressEvent::RefreshApps {
-                    progress: p,
-                    message: msg.to_string(),
-                    finished: false,
-                    error: None,
-                },
-            );
-        })
+    Ok(match source {
+        Some(source) => apps
+            .into_iter()
+            .filter(|a| a.install_source == source)
+            .collect(),
+        None => apps,
     })
-    .await
-    .map_err(|e| e.to_string())?;
-
-    match result {
-        Ok(apps) => {
-            progress::emit(
-                &app,
-                ProgressEvent::RefreshApps {
-                    progress: 1.0,
-                    message: "Done.".into(),
-                    finished: true,
-                    error: None,
-                },
-            );
-            Ok(apps)
-        }
-        Err(e) => {
-            let msg = format!("{e:?}");
-            progress::emit(
-                &app,
-                ProgressEvent::RefreshApps {
-                    progress: 1.0,
-                    message: "Failed.".into(),
-                    finished: true,
-                    error: Some(msg.clone()),
-                },
-            );
-            Err(msg)
-        }
-    }
 }
 
+/// Scan like `list_apps`, but return only apps whose executable has no
+/// arm64 slice (Intel-only, or PowerPC/i386 leftovers).
+#[tauri::command]
+pub async fn list_intel_only_apps(app: AppHandle) -> Result<Vec<AppInfo>, CoreError> {
+    let roots = user_settings(&app)?.user_scan_roots();
+    let apps = tauri::async_runtime::spawn_blocking(move || core::scan_apps(&roots))
+        .await?
+        .map_err(CoreError::from)?;
+    Ok(apps.into_iter().filter(core::lacks_arm64).collect())
+}
+
+/// Scan like `list_apps`, but return only apps not used in the last
+/// `older_than_days` days, least recently used first.
+#[tauri::command]
+pub async fn list_unused_apps(
+    app: AppHandle,
+    older_than_days: u32,
+) -> Result<Vec<AppInfo>, CoreError> {
+    let roots = user_settings(&app)?.user_scan_roots();
+    let apps = tauri::async_runtime::spawn_blocking(move || core::scan_apps(&roots))
+        .await?
+        .map_err(CoreError::from)?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let cutoff = now - i64::from(older_than_days) * 24 * 60 * 60;
+    let mut unused: Vec<AppInfo> = apps
+        .into_iter()
+        .filter(|a| core::is_unused_since(a, cutoff))
+        .collect();
+    unused.sort_by_key(|a| a.last_used_at);
+    Ok(unused)
+}
+
+/// Scan, then group apps installed more than once (same bundle id or same
+/// name modulo channel suffixes) and size the redundant copies.
+#[tauri::command]
+pub async fn find_duplicate_apps(app: AppHandle) -> Result<Vec<DuplicateGroup>, CoreError> {
+    let roots = user_settings(&app)?.user_scan_roots();
+    tauri::async_runtime::spawn_blocking(move || {
+        core::scan_apps(&roots).map(|apps| core::find_duplicate_apps(&apps))
+    })
+    .await?
+    .map_err(CoreError::from)
+}
+
+/// Scan, then check the Sparkle appcast (`SUFeedURL`) of every app that
+/// declares one. Apps without a feed are left out of the result.
+#[tauri::command]
+pub async fn check_for_updates(app: AppHandle) -> Result<Vec<UpdateCheck>, CoreError> {
+    let roots = user_settings(&app)?.user_scan_roots();
+    let sink = TauriSink::new(&app);
+    tauri::async_runtime::spawn_blocking(move || {
+        let task = core::Task::start(
+            &sink,
+            core::ProgressKind::CheckUpdates,
+            "Checking for updates...",
+        );
+        let apps = match core::scan_apps(&roots) {
+            Ok(apps) => apps,
+            Err(e) => {
+                let e = CoreError::from(e);
+                task.fail("Failed.", e.to_string());
+                return Err(e);
+            }
+        };
+        let os_version = sysinfo::System::os_version();
+        let checks = core::check_updates(
+            &core::CurlClient::default(),
+            &apps,
+            os_version.as_deref(),
+            |done, total, name| {
+                task.set_items(done as u64, Some(total as u64));
+                task.progress(
+                    done as f32 / total.max(1) as f32,
+                    format!("Checking {name}..."),
+                );
+            },
+        );
+        let available = checks.iter().filter(|c| c.update_available).count();
+        task.finish(format!("{available} update(s) available."));
+        Ok(checks)
+    })
+    .await?
+}
+
+/// Related files by bundle id and name. When `cask` is given, paths from
+/// the cask's `zap` stanza are merged in.
 #[tauri::command]
 pub async fn find_related(
     app: AppHandle,
     bundle_id: Option<String>,
     app_name: String,
-) -> Result<Vec<PathBuf>, String> {
-    progress::emit(
-        &app,
-        ProgressEvent::FindRelated {
-            progress: 0.0,
-            message: format!("Finding related files for {app_name}..."),
-            finished: false,
-            error: None,
-        },
-    );
-
+    cask: Option<String>,
+) -> Result<Vec<PathBuf>, CoreError> {
+    let max_depth = user_settings(&app)?.related_max_depth;
+    let exclusions = user_exclusions(&app)?;
+    let sink = TauriSink::new(&app);
     let result = tauri::async_runtime::spawn_blocking(move || {
-        core::find_related_paths(bundle_id.as_deref(), Some(&app_name))
+        core::run_find_related(
+            bundle_id.as_deref(),
+            &app_name,
+            cask.as_deref(),
+            max_depth,
+            &exclusions,
+            &sink,
+        )
     })
-    .await
-    .map_err(|e| e.to_string())?;
-
-    progress::emit(
-        &app,
-        ProgressEvent::FindRelated {
-            progress: 1.0,
-            message: format!("Found {} related item(s).", result.len()),
-            finished: true,
-            error: None,
-        },
-    );
-
+    .await?;
     Ok(result)
 }
 
@@ -108,7 +152,7 @@ pub async fn is_app_running(
     app_path: Option<PathBuf>,
     bundle_id: Option<String>,
     app_name: Option<String>,
-) -> Result<bool, String> {
+) -> Result<bool, CoreError> {
     let result = tauri::async_runtime::spawn_blocking(move || {
         core::is_app_running_simple(
             app_path.as_deref(),
@@ -116,40 +160,113 @@ pub async fn is_app_running(
             app_name.as_deref(),
         )
     })
-    .await
-    .map_err(|e| e.to_string())?;
+    .await?;
     Ok(result)
 }
 
-/// Send SIGKILL to every process that matches the given app. Returns the
-/// number of processes that were killed.
+/// Send SIGKILL to every process that matches the given app and wait up to
+/// the configured kill deadline for them to exit. Returns the number of
+/// processes that were killed.
 #[tauri::command]
 pub async fn kill_app(
+    app: AppHandle,
     app_path: Option<PathBuf>,
     bundle_id: Option<String>,
     app_name: Option<String>,
-) -> Result<u32, String> {
+) -> Result<u32, CoreError> {
+    let deadline = user_settings(&app)?.kill_deadline();
     let killed = tauri::async_runtime::spawn_blocking(move || {
         core::kill_app(
             app_path.as_deref(),
             bundle_id.as_deref(),
             app_name.as_deref(),
+            deadline,
         )
     })
-    .await
-    .map_err(|e| e.to_string())?;
+    .await?;
     Ok(killed)
 }
 
 /// Recursively sum the size of every file under `path`. Expensive for large
-/// bundles, so this runs on demand and is not part of `list_apps`.
+/// bundles, so this runs on demand and is not part of `list_apps`. Emits
+/// `size_computation` progress with the files and bytes counted so far.
+#[tauri::command]
+pub async fn get_app_size(app: AppHandle, path: PathBuf) -> Result<Option<u64>, CoreError> {
+    let sink = TauriSink::new(&app);
+    let size = tauri::async_runtime::spawn_blocking(move || {
+        let task = core::Task::start(
+            &sink,
+            core::ProgressKind::SizeComputation,
+            format!("Sizing {}...", path.display()),
+        );
+        let size = core::compute_size_with_progress(&path, |files, bytes| {
+            task.set_items(files, None);
+            task.set_bytes(bytes);
+            task.progress(0.0, format!("Counted {files} file(s)..."));
+        });
+        task.set_bytes(size.unwrap_or(0));
+        task.finish("Done.");
+        size
+    })
+    .await?;
+    Ok(size)
+}
+
+/// Scan, then size each top-level entry of `~/Library`'s Application
+/// Support, Caches, Containers, Group Containers and Logs and attribute it
+/// to an installed app. Emits `refresh_apps`, then `storage_analysis`
+/// progress.
 #[tauri::command]
-pub async fn get_app_size(path: PathBuf) -> Result<Option<u64>, String> {
-    tauri::async_runtime::spawn_blocking(move || core::compute_size(&path))
-        .await
-        .map_err(|e| e.to_string())
+pub async fn analyze_storage(app: AppHandle) -> Result<StorageReport, CoreError> {
+    let roots = user_settings(&app)?.user_scan_roots();
+    let sink = TauriSink::new(&app);
+    tauri::async_runtime::spawn_blocking(move || {
+        let apps = core::run_scan(&roots, &sink)?;
+        Ok(core::analyze_storage(&apps, &sink))
+    })
+    .await?
 }
 
+/// Decode the main executable's code signature: signer, Team ID,
+/// authority chain, hardened runtime, entitlements.
+#[tauri::command]
+pub async fn get_app_security(path: PathBuf) -> Result<AppSecurityInfo, CoreError> {
+    tauri::async_runtime::spawn_blocking(move || core::inspect_app_security(&path))
+        .await?
+        .map_err(CoreError::from)
+}
+
+/// Scan and write an inventory of installed apps to `path`. Returns the
+/// number of apps written. `include_related` adds each app's related paths
+/// with their sizes, which makes the export considerably slower.
+#[tauri::command]
+pub async fn export_inventory(
+    app: AppHandle,
+    format: ExportFormat,
+    path: PathBuf,
+    include_related: Option<bool>,
+) -> Result<usize, CoreError> {
+    let settings = user_settings(&app)?;
+    let exclusions = user_exclusions(&app)?;
+    let sink = TauriSink::new(&app);
+    tauri::async_runtime::spawn_blocking(move || {
+        core::export_inventory(
+            format,
+            &path,
+            include_related.unwrap_or(false),
+            &settings,
+            &exclusions,
+            &sink,
+        )
+    })
+    .await?
+    .map_err(CoreError::from)
+}
+
+/// Apple apps (`AppInfo.protection == Apple`) are refused unless
+/// `allow_protected` is set; apps on the sealed system volume are always
+/// refused. Running apps, and whether removals go through the Trash, follow
+/// the user's settings, as does backing up the related paths first.
 #[tauri::command]
 pub async fn uninstall(
     app: AppHandle,
@@ -157,150 +274,399 @@ pub async fn uninstall(
     app_name: String,
     bundle_id: Option<String>,
     related_paths: Vec<PathBuf>,
-) -> Result<UninstallReport, String> {
-    let app_for_task = app.clone();
+    allow_protected: Option<bool>,
+    via_brew: Option<bool>,
+) -> Result<UninstallReport, CoreError> {
+    let settings = user_settings(&app)?;
+    let options = core::UninstallOptions {
+        allow_protected: allow_protected.unwrap_or(false),
+        via_brew: via_brew.unwrap_or(false),
+        backup_dir: uninstall_backup_dir(&app, &settings)?,
+        ..core::UninstallOptions::from_settings(&settings)
+    };
+    let exclusions = user_exclusions(&app)?;
+    let sink = TauriSink::new(&app);
 
     tauri::async_runtime::spawn_blocking(move || {
-        run_uninstall(&app_for_task, app_path, app_name, bundle_id, related_paths)
+        core::run_uninstall(
+            app_path,
+            app_name,
+            bundle_id,
+            related_paths,
+            options,
+            &exclusions,
+            &sink,
+        )
     })
-    .await
-    .map_err(|e| e.to_string())?
+    .await?
 }
 
-fn run_uninstall(
-    app: &AppHandle,
+/// What `clean_app_data` would remove for these `categories` (all when
+/// omitted), with sizes, without touching anything.
+#[tauri::command]
+pub async fn preview_clean_app_data(
+    app: AppHandle,
+    app_path: PathBuf,
+    app_name: String,
+    categories: Option<Vec<CleanCategory>>,
+) -> Result<Vec<CleanItem>, CoreError> {
+    let max_depth = user_settings(&app)?.related_max_depth;
+    let exclusions = user_exclusions(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        core::plan_clean(
+            &app_path,
+            &app_name,
+            &categories.unwrap_or_default(),
+            max_depth,
+            &exclusions,
+        )
+    })
+    .await?
+}
+
+/// Remove an app's caches, logs, HTTP storage, saved state and crash
+/// reports (or the subset in `categories`) and keep the app, its
+/// preferences and its Application Support data. Refuses while the app is
+/// running. Emits `clean_data` progress.
+#[tauri::command]
+pub async fn clean_app_data(
+    app: AppHandle,
+    app_path: PathBuf,
+    app_name: String,
+    categories: Option<Vec<CleanCategory>>,
+) -> Result<CleanReport, CoreError> {
+    let settings = user_settings(&app)?;
+    let exclusions = user_exclusions(&app)?;
+    let sink = TauriSink::new(&app);
+    tauri::async_runtime::spawn_blocking(move || {
+        core::clean_app_data(
+            app_path,
+            &app_name,
+            &categories.unwrap_or_default(),
+            &settings,
+            &exclusions,
+            &sink,
+        )
+    })
+    .await?
+}
+
+/// Force-quit the app, then remove its preferences, container, saved state
+/// and Application Support data so it starts as if freshly installed. The
+/// bundle stays. `backup` first archives everything into the backups
+/// folder. Emits `reset_app` progress.
+#[tauri::command]
+pub async fn reset_app(
+    app: AppHandle,
     app_path: PathBuf,
     app_name: String,
     bundle_id: Option<String>,
-    related_paths: Vec<PathBuf>,
-) -> Result<UninstallReport, String> {
-    let emit_progress = |progress: f32, message: String, finished: bool, error: Option<String>| {
-        progress::emit(
-            app,
-            ProgressEvent::Uninstall {
-                progress,
-                message,
-                finished,
-                error,
-            },
-        );
+    backup: Option<bool>,
+) -> Result<ResetReport, CoreError> {
+    let settings = user_settings(&app)?;
+    let options = core::ResetOptions {
+        backup_dir: if backup.unwrap_or(false) {
+            Some(backups_dir(&app, &settings)?)
+        } else {
+            None
+        },
+        ..core::ResetOptions::from_settings(&settings)
     };
+    let exclusions = user_exclusions(&app)?;
+    let sink = TauriSink::new(&app);
+    tauri::async_runtime::spawn_blocking(move || {
+        core::reset_app(app_path, app_name, bundle_id, options, &exclusions, &sink)
+    })
+    .await?
+}
 
-    emit_progress(
-        0.0,
-        format!("Starting uninstall of {app_name}..."),
-        false,
-        None,
-    );
-
-    if core::is_app_running_simple(Some(&app_path), bundle_id.as_deref(), Some(&app_name)) {
-        let err = "App is running. Abort uninstall.".to_string();
-        emit_progress(0.0, err.clone(), true, Some(err.clone()));
-        return Err(err);
-    }
+/// Load an uninstall profile (TOML or JSON), resolve it against the
+/// installed apps and run the uninstall pipeline for each match. Running
+/// apps are killed first when the profile sets `kill_running`, unless they
+/// are protected or excluded; otherwise they are handled like a regular
+/// `uninstall`. `dry_run` only reports the
+/// plan.
+#[tauri::command]
+pub async fn apply_profile(
+    app: AppHandle,
+    path: PathBuf,
+    dry_run: Option<bool>,
+) -> Result<ProfileReport, CoreError> {
+    tauri::async_runtime::spawn_blocking(move || run_profile(&app, &path, dry_run.unwrap_or(false)))
+        .await?
+}
 
-    let total_steps = 1 + related_paths.len();
-    let mut step = 0usize;
-    let mut report = UninstallReport {
-        app_path: app_path.clone(),
-        removed: Vec::new(),
-        failed: Vec::new(),
-        aborted: false,
+fn run_profile(app: &AppHandle, path: &Path, dry_run: bool) -> Result<ProfileReport, CoreError> {
+    let profile = core::load_profile(path)?;
+    let settings = user_settings(app)?;
+    let exclusions = user_exclusions(app)?;
+    let options = core::UninstallOptions {
+        backup_dir: uninstall_backup_dir(app, &settings)?,
+        ..core::UninstallOptions::from_settings(&settings)
     };
+    let sink = TauriSink::new(app);
+    let apps = core::scan_apps(&settings.user_scan_roots())?;
+    let casks = core::scan_casks();
+    let plan = core::resolve_profile_for_user(&profile, &apps, |a| {
+        let mut paths = core::find_related_paths(
+            a.bundle_id.as_deref(),
+            Some(&a.name),
+            settings.related_max_depth,
+            &exclusions,
+        );
+        core::merge_zap_paths(&casks, a.cask.as_deref(), &mut paths);
+        paths.retain(|p| !exclusions.is_excluded(p));
+        paths
+    });
 
-    // Step 1: bundle itself.
-    if let Err(e) = core::move_to_trash_or_remove(&app_path) {
-        let msg = format!("Failed to remove bundle: {e:?}");
-        emit_progress(0.0, msg.clone(), true, Some(msg.clone()));
-        report.aborted = true;
-        report.failed.push(UninstallFailure {
-            path: app_path.clone(),
-            error: msg.clone(),
-        });
-        return Err(msg);
-    }
-    step += 1;
-    report.removed.push(app_path.clone());
-    emit_progress(
-        step as f32 / total_steps as f32,
-        format!("Moved {} to Trash", app_path.display()),
-        false,
-        None,
-    );
-
-    // Step 2: split into protected vs unprotected.
-    let (protected, unprotected): (Vec<PathBuf>, Vec<PathBuf>) = related_paths
-        .into_iter()
-        .partition(|p| core::is_protected_path(p));
-
-    // Phase 2a: protected — abort on first failure.
-    for p in protected {
-        match core::move_to_trash_or_remove(&p) {
-            Ok(()) => {
-                step += 1;
-                report.removed.push(p.clone());
-                emit_progress(
-                    step as f32 / total_steps as f32,
-                    format!("Removed {}", p.display()),
-                    false,
-                    None,
+    let mut report = ProfileReport {
+        profile_name: profile.name,
+        dry_run,
+        apps: Vec::new(),
+        unmatched: plan.unmatched,
+        extra_removed: Vec::new(),
+        extra_failed: Vec::new(),
+    };
+    for planned in plan.apps {
+        let target = planned.app;
+        let mut result = ProfileAppResult {
+            app_path: target.path.clone(),
+            name: target.name.clone(),
+            related: planned.related.clone(),
+            kept: planned.kept,
+            killed: 0,
+            report: None,
+            error: None,
+        };
+        if !dry_run {
+            // Only quit apps `run_uninstall` won't refuse outright.
+            let refused =
+                target.protection != AppProtection::None || exclusions.is_excluded(&target.path);
+            if target.running && plan.kill_running && !refused {
+                result.killed = core::kill_app(
+                    Some(&target.path),
+                    target.bundle_id.as_deref(),
+                    Some(&target.name),
+                    settings.kill_deadline(),
                 );
             }
-            Err(e) => {
-                let msg = format!("Aborting on {}: {:?}", p.display(), e);
-                report.aborted = true;
-                report.failed.push(UninstallFailure {
-                    path: p,
-                    error: format!("{e:?}"),
-                });
-                emit_progress(
-                    step as f32 / total_steps as f32,
-                    msg.clone(),
-                    true,
-                    Some(msg.clone()),
-                );
-                return Err(msg);
+            match core::run_uninstall(
+                target.path,
+                target.name,
+                target.bundle_id,
+                planned.related,
+                options.clone(),
+                &exclusions,
+                &sink,
+            ) {
+                Ok(r) => result.report = Some(r),
+                Err(e) => result.error = Some(e.to_string()),
             }
         }
+        report.apps.push(result);
     }
 
-    // Phase 2b: unprotected — continue past per-item errors.
-    for p in unprotected {
-        match core::move_to_trash_or_remove(&p) {
-            Ok(()) => {
-                step += 1;
-                report.removed.push(p.clone());
-                emit_progress(
-                    step as f32 / total_steps as f32,
-                    format!("Removed {}", p.display()),
-                    false,
-                    None,
-                );
-            }
-            Err(e) => {
-                let msg = format!("Failed to remove {}: {:?}", p.display(), e);
-                report.failed.push(UninstallFailure {
-                    path: p,
-                    error: format!("{e:?}"),
-                });
-                emit_progress(
-                    step as f32 / total_steps as f32,
-                    msg,
-                    false,
-                    Some(format!("{e:?}")),
-                );
-            }
+    for p in plan.extra_paths {
+        if let Some(rule) = exclusions.matching_rule(&p) {
+            let reason = format!("Excluded by rule {rule:?}");
+            report.extra_failed.push(UninstallFailure {
+                path: p,
+                error: reason.clone(),
+                kind: FailureKind::Refused { reason },
+            });
+            continue;
+        }
+        if dry_run {
+            report.extra_removed.push(p);
+            continue;
+        }
+        match core::remove_path(&p, settings.removal_mode) {
+            Ok(()) => report.extra_removed.push(p),
+            Err(e) => report.extra_failed.push(core::uninstall::failure(p, &e)),
         }
     }
+    Ok(report)
+}
 
-    emit_progress(1.0, "Uninstall complete".into(), true, None);
+/// The renames `migrate_app_data` would make from `from_bundle_id`'s data
+/// to `to_bundle_id`'s, with blocked ones explained, without touching
+/// anything.
+#[tauri::command]
+pub async fn preview_migrate_app_data(
+    app: AppHandle,
+    from_bundle_id: String,
+    to_bundle_id: String,
+) -> Result<Vec<MigrationMove>, CoreError> {
+    let exclusions = user_exclusions(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        core::plan_migration(&from_bundle_id, &to_bundle_id, &exclusions)
+    })
+    .await?
+}
+
+/// Move an app's Application Support folder, preferences and container
+/// data from one bundle id to another, e.g. from a beta to the stable
+/// build. Existing destinations are never overwritten; refuses while either
+/// app is running.
+#[tauri::command]
+pub async fn migrate_app_data(
+    app: AppHandle,
+    from_bundle_id: String,
+    to_bundle_id: String,
+) -> Result<MigrationReport, CoreError> {
+    let exclusions = user_exclusions(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        core::migrate_app_data(&from_bundle_id, &to_bundle_id, &exclusions)
+    })
+    .await?
+}
+
+/// Put back everything a backup archive (from `uninstall` or `reset_app`)
+/// holds, at its original location. Entries whose location is taken again
+/// are skipped and reported as refused.
+#[tauri::command]
+pub async fn restore_backup(archive: PathBuf) -> Result<RestoreReport, CoreError> {
+    tauri::async_runtime::spawn_blocking(move || core::restore_backup(&archive))
+        .await?
+        .map_err(CoreError::from)
+}
+
+/// `settings.backups_dir`, or `backups` in the app data folder.
+fn backups_dir(app: &AppHandle, settings: &Settings) -> Result<PathBuf, CoreError> {
+    let home = core::user_home();
+    match settings.backups_path(&home) {
+        Some(dir) => Ok(dir),
+        None => app
+            .path()
+            .app_data_dir()
+            .map(|dir| dir.join("backups"))
+            .map_err(CoreError::from),
+    }
+}
+
+/// Where `uninstall` backs up to, if `backup_before_uninstall` is on.
+fn uninstall_backup_dir(
+    app: &AppHandle,
+    settings: &Settings,
+) -> Result<Option<PathBuf>, CoreError> {
+    if settings.backup_before_uninstall {
+        backups_dir(app, settings).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+fn exclusions_path(app: &AppHandle) -> Result<PathBuf, CoreError> {
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join(core::EXCLUSIONS_FILE))
+        .map_err(CoreError::from)
+}
+
+fn user_exclusions(app: &AppHandle) -> Result<core::Exclusions, CoreError> {
+    core::load_user_exclusions(&exclusions_path(app)?).map_err(CoreError::from)
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf, CoreError> {
+    app.path()
+        .app_config_dir()
+        .map(|dir| dir.join(core::SETTINGS_FILE))
+        .map_err(CoreError::from)
+}
+
+fn user_settings(app: &AppHandle) -> Result<Settings, CoreError> {
+    core::load_settings(&settings_path(app)?).map_err(CoreError::from)
+}
+
+/// The user's settings, migrated to the current schema; defaults when
+/// nothing has been saved yet. See `core::settings`.
+#[tauri::command]
+pub async fn get_settings(app: AppHandle) -> Result<Settings, CoreError> {
+    let path = settings_path(&app)?;
+    tauri::async_runtime::spawn_blocking(move || core::load_settings(&path))
+        .await?
+        .map_err(CoreError::from)
+}
+
+/// Validate and save `settings`. Invalid values are rejected and nothing is
+/// written. Returns the saved settings.
+#[tauri::command]
+pub async fn update_settings(app: AppHandle, settings: Settings) -> Result<Settings, CoreError> {
+    let path = settings_path(&app)?;
+    tauri::async_runtime::spawn_blocking(move || core::save_settings(&path, &settings))
+        .await?
+        .map_err(CoreError::from)
+}
+
+/// The user's exclusion list; see `core::exclusions`.
+#[tauri::command]
+pub async fn get_exclusions(app: AppHandle) -> Result<ExclusionRules, CoreError> {
+    let path = exclusions_path(&app)?;
+    tauri::async_runtime::spawn_blocking(move || core::load_exclusions(&path))
+        .await?
+        .map_err(CoreError::from)
+}
+
+/// Replace the exclusion list. Invalid globs are rejected and nothing is
+/// written. Returns the saved rules.
+#[tauri::command]
+pub async fn set_exclusions(
+    app: AppHandle,
+    rules: ExclusionRules,
+) -> Result<ExclusionRules, CoreError> {
+    let path = exclusions_path(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        core::save_exclusions(&path, &rules).map(|()| rules)
+    })
+    .await?
+    .map_err(CoreError::from)
+}
+
+/// Failures of the Tauri runtime itself: a blocking task that was cancelled
+/// (the runtime shutting down before it ran) or panicked, an unresolvable
+/// app directory.
+impl From<tauri::Error> for CoreError {
+    fn from(e: tauri::Error) -> Self {
+        match e {
+            tauri::Error::JoinError(j) if j.is_cancelled() => CoreError::Cancelled,
+            e => CoreError::other(e.to_string()),
+        }
+    }
+}
+
+/// Strip every universal binary in the bundle down to `architecture`,
+/// moving the originals to the Trash. Signed bundles are refused unless
+/// `accept_invalid_signature` is set.
+#[tauri::command]
+pub async fn thin_app(
+    app_path: PathBuf,
+    architecture: Architecture,
+    accept_invalid_signature: Option<bool>,
+) -> Result<ThinReport, CoreError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        core::thin_app(
+            &app_path,
+            architecture,
+            accept_invalid_signature.unwrap_or(false),
+        )
+    })
+    .await?
+    .map_err(CoreError::from)
+}
+
+/// Probe read/write access to the Library locations the scan and uninstall
+/// touch, plus `paths`, and detect whether Full Disk Access is granted.
+/// Cheap enough to run before every scan.
+#[tauri::command]
+pub async fn check_access(paths: Option<Vec<PathBuf>>) -> Result<AccessReport, CoreError> {
+    let report = tauri::async_runtime::spawn_blocking(move || {
+        core::check_access(&paths.unwrap_or_default())
+    })
+    .await?;
     Ok(report)
 }
 
 #[tauri::command]
-pub async fn reveal_in_finder(path: PathBuf) -> Result<(), String> {
-    tauri::async_runtime::spawn_blocking(move || core::reveal_in_finder(&path))
-        .await
-        .map_err(|e| e.to_string())?
-        .map_err(|e| format!("{e:?}"))
+pub async fn reveal_in_finder(path: PathBuf) -> Result<(), CoreError> {
+    tauri::async_runtime::spawn_blocking(move || core::reveal_in_finder(&path)).await?
 }
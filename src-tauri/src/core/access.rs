@@ -0,0 +1,175 @@
+//! Pre-flight permission checks.
+//!
+//! Much of `~/Library` is guarded by TCC: without Full Disk Access,
+//! listing another app's container fails with `EPERM`, which
+//! `find_related_paths` can't tell apart from an empty folder. Probing the
+//! locations up front lets the UI ask for access before scanning instead
+//! of reporting a short list or an opaque removal error.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::paths::user_home;
+use crate::models::{AccessReport, FullDiskAccess, LocationAccess};
+
+/// Folders only readable with Full Disk Access. At least one of them exists
+/// on any Mac that has been used.
+const FDA_PROBES: &[&str] = &[
+    "Library/Application Support/com.apple.TCC",
+    "Library/Safari",
+    "Library/Mail",
+    "Library/Messages",
+];
+
+/// Folders `find_related_paths` and `uninstall` read from and remove in.
+pub fn library_locations(home: &Path) -> Vec<PathBuf> {
+    let lib = home.join("Library");
+    let mut v: Vec<PathBuf> = [
+        "Application Support",
+        "Caches",
+        "Preferences",
+        "Containers",
+        "Group Containers",
+        "Logs",
+        "LaunchAgents",
+    ]
+    .iter()
+    .map(|d| lib.join(d))
+    .collect();
+    v.push(PathBuf::from("/Library/Receipts"));
+    v.push(PathBuf::from("/private/var/db/receipts"));
+    v
+}
+
+/// Create and remove a probe file in `dir`. Permission bits alone miss ACLs,
+/// TCC and read-only volumes, so this is the only reliable answer.
+pub fn probe_writable(dir: &Path) -> io::Result<()> {
+    let probe = dir.join(format!(".uninstall-probe-{}", std::process::id()));
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&probe)?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+/// Probe read and write access to `path`. Folders are listed and get a
+/// probe file; files are opened, for writing without truncating.
+pub fn probe_location(path: &Path) -> LocationAccess {
+    let mut access = LocationAccess {
+        path: path.to_path_buf(),
+        exists: false,
+        readable: false,
+        writable: false,
+        error: None,
+    };
+    let meta = match fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return access,
+        Err(e) => {
+            access.error = Some(e.to_string());
+            return access;
+        }
+    };
+    access.exists = true;
+
+    let (read, write) = if meta.is_dir() {
+        (
+            fs::read_dir(path).and_then(|mut it| it.next().transpose().map(|_| ())),
+            probe_writable(path),
+        )
+    } else {
+        (
+            fs::File::open(path).map(|_| ()),
+            fs::OpenOptions::new().write(true).open(path).map(|_| ()),
+        )
+    };
+    access.readable = read.is_ok();
+    access.writable = write.is_ok();
+    access.error = read.err().or(write.err()).map(|e| e.to_string());
+    access
+}
+
+/// Any probe that could be read means access; one that exists but is
+/// refused means it's missing.
+fn classify(results: impl IntoIterator<Item = io::Result<()>>) -> FullDiskAccess {
+    let mut status = FullDiskAccess::Unknown;
+    for r in results {
+        match r {
+            Ok(()) => return FullDiskAccess::Granted,
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                status = FullDiskAccess::Missing;
+            }
+            Err(_) => {}
+        }
+    }
+    status
+}
+
+/// Detect Full Disk Access by listing folders TCC only opens with it.
+pub fn full_disk_access(home: &Path) -> FullDiskAccess {
+    classify(
+        FDA_PROBES
+            .iter()
+            .map(|p| fs::read_dir(home.join(p)).map(|_| ())),
+    )
+}
+
+/// Full Disk Access status plus a probe of every Library location and of
+/// `extra` (e.g. the related paths about to be removed).
+pub fn check_access_in(home: &Path, extra: &[PathBuf]) -> AccessReport {
+    let mut paths = library_locations(home);
+    paths.extend(extra.iter().cloned());
+    paths.dedup();
+    AccessReport {
+        full_disk_access: full_disk_access(home),
+        locations: paths.iter().map(|p| probe_location(p)).collect(),
+    }
+}
+
+/// [`check_access_in`] for the current user.
+pub fn check_access(extra: &[PathBuf]) -> AccessReport {
+    let home = user_home();
+    check_access_in(&home, extra)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_full_disk_access() {
+        let denied = || Err(io::Error::from(io::ErrorKind::PermissionDenied));
+        let missing = || Err(io::Error::from(io::ErrorKind::NotFound));
+        assert_eq!(classify([missing(), missing()]), FullDiskAccess::Unknown);
+        assert_eq!(classify([missing(), denied()]), FullDiskAccess::Missing);
+        assert_eq!(classify([denied(), Ok(())]), FullDiskAccess::Granted);
+    }
+
+    #[test]
+    fn probes_locations_under_home() {
+        let home = std::env::temp_dir().join(format!("access-test-{}", std::process::id()));
+        let caches = home.join("Library").join("Caches");
+        fs::create_dir_all(&caches).unwrap();
+        fs::write(caches.join("c.db"), "x").unwrap();
+
+        let before = full_disk_access(&home);
+        fs::create_dir_all(home.join("Library").join("Safari")).unwrap();
+        let report = check_access_in(&home, &[caches.join("c.db")]);
+        let leftovers = fs::read_dir(&caches).unwrap().count();
+        fs::remove_dir_all(&home).unwrap();
+
+        assert_eq!(before, FullDiskAccess::Unknown);
+        assert_eq!(report.full_disk_access, FullDiskAccess::Granted);
+        let find = |p: &Path| report.locations.iter().find(|l| l.path == p).unwrap();
+        let dir = find(&caches);
+        assert!(dir.exists && dir.readable && dir.writable, "{dir:?}");
+        assert!(find(&caches.join("c.db")).writable);
+        let logs = find(&home.join("Library").join("Logs"));
+        assert!(!logs.exists && logs.error.is_none());
+        assert_eq!(leftovers, 1, "probe file removed");
+    }
+}
@@ -1,33 +1,56 @@
 //! Scan installed application bundles.
 
 use anyhow::{Context, Result};
-use home::home_dir;
-use std::{fs, path::PathBuf};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
 use sysinfo::{ProcessRefreshKind, RefreshKind, System, UpdateKind};
 use walkdir::WalkDir;
 
-use super::{plist_info::read_info_from_app, running::is_app_running};
+use super::{
+    casks::{Cask, cask_for_app, scan_casks},
+    macho::read_architectures,
+    plist_info::read_info_from_app,
+    progress::{ProgressKind, ProgressSink, Task},
+    protection::app_protection,
+    running::is_app_running,
+    source::{RECEIPT_DIRS, install_source, pkg_installed_apps},
+    usage::last_used_at,
+    version::compare_bundle_versions,
+};
 use crate::models::AppInfo;
 
-pub fn candidate_app_dirs() -> Vec<PathBuf> {
-    vec![
-        PathBuf::from("/Applications"),
-        home_dir()
-            .map(|h| h.join("Applications"))
-            .unwrap_or_default(),
-    ]
+/// Scan `roots` (normally `Settings::user_scan_roots`) for `.app` bundles.
+pub fn scan_apps(roots: &[PathBuf]) -> Result<Vec<AppInfo>> {
+    scan_apps_with_progress(roots, |_, _| {})
 }
 
-pub fn scan_apps() -> Result<Vec<AppInfo>> {
-    scan_apps_with_progress(|_, _| {})
+/// [`scan_apps`] as a `refresh_apps` task reported to `sink`.
+pub fn run_scan(roots: &[PathBuf], sink: &dyn ProgressSink) -> Result<Vec<AppInfo>> {
+    let task = Task::start(
+        sink,
+        ProgressKind::RefreshApps,
+        "Scanning application folders...",
+    );
+    match scan_apps_with_progress(roots, |p, msg| task.progress(p, msg)) {
+        Ok(apps) => {
+            task.finish("Done.");
+            Ok(apps)
+        }
+        Err(e) => {
+            task.fail("Failed.", format!("{e:#}"));
+            Err(e)
+        }
+    }
 }
 
-pub fn scan_apps_with_progress<F>(mut on_progress: F) -> Result<Vec<AppInfo>>
+pub fn scan_apps_with_progress<F>(roots: &[PathBuf], mut on_progress: F) -> Result<Vec<AppInfo>>
 where
     F: FnMut(f32, &str),
 {
-    let candidates = candidate_app_dirs();
-    let total = candidates.len().max(1);
+    let total = roots.len().max(1);
 
     // Collect process metadata once for the whole scan. Restrict the snapshot
     // to the fields `is_app_running` reads (exe path + cmdline) so we don't
@@ -38,26 +61,42 @@ where
             .with_cmd(UpdateKind::OnlyIfNotSet),
     );
     let sys = System::new_with_specifics(kind);
+    let casks = scan_casks();
+    let receipts: Vec<PathBuf> = RECEIPT_DIRS.iter().map(PathBuf::from).collect();
+    let pkg_apps = pkg_installed_apps(&receipts);
 
     let mut res = Vec::new();
-    for (idx, dir) in candidates.into_iter().enumerate() {
+    for (idx, dir) in roots.iter().enumerate() {
         on_progress(
             (idx as f32) / (total as f32),
             &format!("Scanning {}", dir.display()),
         );
 
         if dir.exists() && dir.is_dir() {
-            let mut v = scan_one_dir(&sys, &dir)?;
+            let mut v = scan_one_dir(&sys, &casks, &pkg_apps, dir)?;
             res.append(&mut v);
         }
     }
 
-    res.sort_by(|a, b| a.name.cmp(&b.name));
+    // By name, then newest version first so duplicate installs line up.
+    res.sort_by(|a, b| {
+        a.name.cmp(&b.name).then_with(|| {
+            compare_bundle_versions(
+                (b.version.as_deref(), b.build_version.as_deref()),
+                (a.version.as_deref(), a.build_version.as_deref()),
+            )
+        })
+    });
     on_progress(0.95, "Finalizing");
     Ok(res)
 }
 
-fn scan_one_dir(sys: &System, dir: &std::path::Path) -> Result<Vec<AppInfo>> {
+fn scan_one_dir(
+    sys: &System,
+    casks: &[Cask],
+    pkg_apps: &HashSet<String>,
+    dir: &std::path::Path,
+) -> Result<Vec<AppInfo>> {
     let mut res = Vec::new();
     for entry in fs::read_dir(dir).with_context(|| format!("Read dir {}", dir.display()))? {
         let e = entry?;
@@ -71,12 +110,28 @@ fn scan_one_dir(sys: &System, dir: &std::path::Path) -> Result<Vec<AppInfo>> {
                 info.bundle_name.as_deref(),
                 info.executable.as_deref(),
             );
-            let modified_at = e
-                .metadata()
-                .ok()
+            let meta = e.metadata().ok();
+            let modified_at = meta
+                .as_ref()
                 .and_then(|m| m.modified().ok())
                 .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
                 .map(|d| d.as_secs() as i64);
+            let last_used_at = last_used_at(
+                &p,
+                meta.as_ref(),
+                info.bundle_id.as_deref(),
+                info.bundle_name.as_deref(),
+            );
+            let protection =
+                app_protection(&p, info.bundle_id.as_deref(), info.executable.as_deref());
+            let architectures = info
+                .executable
+                .as_deref()
+                .map(|exe| read_architectures(&p.join("Contents").join("MacOS").join(exe)))
+                .unwrap_or_default();
+            let cask = cask_for_app(casks, &p).map(|c| c.token.clone());
+            let install_source =
+                install_source(&p, info.bundle_id.as_deref(), cask.as_deref(), pkg_apps);
             res.push(AppInfo {
                 name: info
                     .bundle_name
@@ -84,9 +139,15 @@ fn scan_one_dir(sys: &System, dir: &std::path::Path) -> Result<Vec<AppInfo>> {
                     .unwrap_or_else(|| p.file_name().unwrap().to_string_lossy().to_string()),
                 bundle_id: info.bundle_id,
                 version: info.version,
+                build_version: info.build_version,
                 executable: info.executable,
                 modified_at,
+                last_used_at,
                 running,
+                protection,
+                architectures,
+                cask,
+                install_source,
                 path: p,
             });
         }
@@ -94,12 +155,59 @@ fn scan_one_dir(sys: &System, dir: &std::path::Path) -> Result<Vec<AppInfo>> {
     Ok(res)
 }
 
+/// The `Info.plist` fields and modification time of the bundle at `path`,
+/// for recording which app a backup belongs to. Skips the process, cask
+/// and receipt lookups a scan does, so `running`, `cask`,
+/// `install_source` and the like keep their defaults.
+pub fn read_app(path: &Path) -> AppInfo {
+    let info = read_info_from_app(path).unwrap_or_default();
+    let modified_at = fs::symlink_metadata(path)
+        .ok()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64);
+    AppInfo {
+        name: info.bundle_name.unwrap_or_else(|| {
+            path.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        }),
+        bundle_id: info.bundle_id,
+        version: info.version,
+        build_version: info.build_version,
+        executable: info.executable,
+        modified_at,
+        path: path.to_path_buf(),
+        ..Default::default()
+    }
+}
+
+/// True if the app's executable is a Mach-O without an arm64/arm64e slice,
+/// i.e. it only runs on Apple silicon through Rosetta.
+pub fn lacks_arm64(app: &AppInfo) -> bool {
+    !app.architectures.is_empty() && !app.architectures.iter().any(|a| a.is_arm64())
+}
+
 /// Sum of regular-file sizes under `path`. Best-effort: skips entries that
 /// can't be read (symlinks pointing into protected dirs, etc.). Expensive for
 /// large bundles (e.g. Xcode), so it's exposed as its own command and called
 /// lazily by the frontend when an app is selected — never during the scan.
 pub fn compute_size(path: &std::path::Path) -> Option<u64> {
+    compute_size_with_progress(path, |_, _| {})
+}
+
+/// Files counted between two `on_progress` calls.
+const SIZE_PROGRESS_EVERY: u64 = 512;
+
+/// [`compute_size`], reporting `(files, bytes)` so far every few hundred
+/// files.
+pub fn compute_size_with_progress<F>(path: &std::path::Path, mut on_progress: F) -> Option<u64>
+where
+    F: FnMut(u64, u64),
+{
     let mut total: u64 = 0;
+    let mut files: u64 = 0;
     for entry in WalkDir::new(path).follow_links(false) {
         let entry = match entry {
             Ok(e) => e,
@@ -108,6 +216,10 @@ pub fn compute_size(path: &std::path::Path) -> Option<u64> {
         if let Ok(meta) = entry.metadata() {
             if meta.is_file() {
                 total = total.saturating_add(meta.len());
+                files += 1;
+                if files % SIZE_PROGRESS_EVERY == 0 {
+                    on_progress(files, total);
+                }
             }
         }
     }
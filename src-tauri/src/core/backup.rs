@@ -0,0 +1,508 @@
+//! Archive paths before removing them, and put them back.
+//!
+//! A backup is a zstd-compressed tar or a zip (see [`BackupFormat`])
+//! holding `manifest.json` (see [`BackupManifest`]) and one
+//! `files/<n>/<name>` tree per archived path, so two paths with the same
+//! file name never collide. Symlinks are stored as links, not followed.
+
+use anyhow::{Context, Result, bail};
+use std::{
+    fs, io,
+    path::{Component, Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use walkdir::WalkDir;
+
+use super::trash::RemovalPolicy;
+use crate::models::{
+    AppInfo, BackupEntry, BackupFormat, BackupManifest, FailureKind, RestoreReport,
+    UninstallFailure,
+};
+
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+impl BackupFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            BackupFormat::TarZst => "tar.zst",
+            BackupFormat::Zip => "zip",
+        }
+    }
+
+    /// Format of `archive`, from its file name.
+    pub fn of(archive: &Path) -> Result<Self> {
+        let name = archive.file_name().unwrap_or_default().to_string_lossy();
+        [BackupFormat::TarZst, BackupFormat::Zip]
+            .into_iter()
+            .find(|f| name.ends_with(&format!(".{}", f.extension())))
+            .with_context(|| format!("{} is not a backup archive", archive.display()))
+    }
+}
+
+/// `label` with anything but letters, digits, `-`, `_` and `.` replaced, so
+/// it is safe in a file name.
+fn file_label(label: &str) -> String {
+    label
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Archive `paths` into `dir/<label>-<timestamp>.<ext>` and return the
+/// archive's path; a name already taken gets a `-2`, `-3`... suffix, so an
+/// existing backup is never overwritten. `app` is recorded in the manifest.
+/// Paths that no longer exist are skipped; any other read error fails the
+/// whole backup and removes the partial archive.
+pub fn create_backup(
+    dir: &Path,
+    label: &str,
+    paths: &[PathBuf],
+    app: Option<&AppInfo>,
+    format: BackupFormat,
+) -> Result<PathBuf> {
+    fs::create_dir_all(dir).with_context(|| format!("Create {}", dir.display()))?;
+    let created_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let (archive, file) = create_archive_file(dir, &file_label(label), created_at, format)?;
+
+    let mut manifest = BackupManifest {
+        created_at,
+        label: label.to_string(),
+        app: app.cloned(),
+        entries: Vec::new(),
+    };
+    for (i, path) in paths.iter().enumerate() {
+        if fs::symlink_metadata(path).is_ok() {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            manifest.entries.push(BackupEntry {
+                original: path.clone(),
+                archived: format!("files/{i}/{name}"),
+            });
+        }
+    }
+
+    let result = match format {
+        BackupFormat::TarZst => write_tar(file, &manifest),
+        BackupFormat::Zip => write_zip(file, &manifest),
+    };
+    if result.is_err() {
+        let _ = fs::remove_file(&archive);
+    }
+    result.map(|()| archive)
+}
+
+/// Create a new, uniquely named archive file in `dir`.
+fn create_archive_file(
+    dir: &Path,
+    label: &str,
+    created_at: i64,
+    format: BackupFormat,
+) -> Result<(PathBuf, fs::File)> {
+    let mut n = 1;
+    loop {
+        let name = match n {
+            1 => format!("{label}-{created_at}.{}", format.extension()),
+            n => format!("{label}-{created_at}-{n}.{}", format.extension()),
+        };
+        n += 1;
+        let path = dir.join(name);
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e).with_context(|| format!("Create {}", path.display())),
+        }
+    }
+}
+
+fn write_tar(file: fs::File, manifest: &BackupManifest) -> Result<()> {
+    let encoder = zstd::Encoder::new(file, 0)?.auto_finish();
+    let mut tar = tar::Builder::new(encoder);
+    tar.follow_symlinks(false);
+
+    for entry in &manifest.entries {
+        let path = &entry.original;
+        if fs::symlink_metadata(path)?.is_dir() {
+            tar.append_dir_all(&entry.archived, path)
+        } else {
+            tar.append_path_with_name(path, &entry.archived)
+        }
+        .with_context(|| format!("Archive {}", path.display()))?;
+    }
+
+    let json = serde_json::to_vec_pretty(manifest)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(json.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(manifest.created_at.max(0) as u64);
+    tar.append_data(&mut header, MANIFEST_NAME, json.as_slice())?;
+    tar.into_inner()?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn unix_mode(meta: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+fn unix_mode(meta: &fs::Metadata) -> u32 {
+    if meta.is_dir() { 0o755 } else { 0o644 }
+}
+
+fn write_zip(file: fs::File, manifest: &BackupManifest) -> Result<()> {
+    use zip::write::SimpleFileOptions;
+
+    let mut zip = zip::ZipWriter::new(file);
+    let options = SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .large_file(true);
+
+    for entry in &manifest.entries {
+        let root = &entry.original;
+        for item in WalkDir::new(root).follow_links(false) {
+            let item = item.with_context(|| format!("Archive {}", root.display()))?;
+            let rel = item.path().strip_prefix(root)?;
+            let name = if rel.as_os_str().is_empty() {
+                entry.archived.clone()
+            } else {
+                format!("{}/{}", entry.archived, rel.to_string_lossy())
+            };
+            let meta = fs::symlink_metadata(item.path())?;
+            let opts = options.unix_permissions(unix_mode(&meta));
+            if meta.is_symlink() {
+                let target = fs::read_link(item.path())?;
+                zip.add_symlink(name, target.to_string_lossy(), opts)?;
+            } else if meta.is_dir() {
+                zip.add_directory(name, opts)?;
+            } else {
+                zip.start_file(name, opts)?;
+                let mut src = fs::File::open(item.path())
+                    .with_context(|| format!("Archive {}", item.path().display()))?;
+                io::copy(&mut src, &mut zip)?;
+            }
+        }
+    }
+
+    zip.start_file(MANIFEST_NAME, options.unix_permissions(0o644))?;
+    serde_json::to_writer_pretty(&mut zip, manifest)?;
+    zip.finish()?;
+    Ok(())
+}
+
+/// Read the manifest of a backup created by [`create_backup`].
+pub fn read_manifest(archive: &Path) -> Result<BackupManifest> {
+    let file = fs::File::open(archive).with_context(|| format!("Open {}", archive.display()))?;
+    let parse = |r: &mut dyn io::Read| {
+        serde_json::from_reader(r)
+            .with_context(|| format!("Parse manifest of {}", archive.display()))
+    };
+    match BackupFormat::of(archive)? {
+        BackupFormat::TarZst => {
+            let mut tar = tar::Archive::new(zstd::Decoder::new(file)?);
+            for entry in tar.entries()? {
+                let mut entry = entry?;
+                if entry.path()?.as_os_str() == MANIFEST_NAME {
+                    return parse(&mut entry);
+                }
+            }
+            bail!("{} has no {MANIFEST_NAME}", archive.display())
+        }
+        BackupFormat::Zip => {
+            let mut zip = zip::ZipArchive::new(file)?;
+            let mut entry = zip
+                .by_name(MANIFEST_NAME)
+                .with_context(|| format!("{} has no {MANIFEST_NAME}", archive.display()))?;
+            parse(&mut entry)
+        }
+    }
+}
+
+/// Whether `archived` is a `files/<n>/<name>` path, so a tampered manifest
+/// can't point outside the extracted tree.
+fn is_archived_path(archived: &str) -> bool {
+    let parts: Vec<Component> = Path::new(archived).components().collect();
+    matches!(parts.as_slice(), [Component::Normal(f), Component::Normal(_), Component::Normal(_)] if *f == "files")
+}
+
+/// Copy `src` to `dest` recursively, keeping symlinks as links. Used when
+/// the original location is on another volume than the backups folder.
+fn copy_tree(src: &Path, dest: &Path) -> io::Result<()> {
+    for item in WalkDir::new(src).follow_links(false) {
+        let item = item?;
+        let rel = item.path().strip_prefix(src).map_err(io::Error::other)?;
+        let to = if rel.as_os_str().is_empty() {
+            dest.to_path_buf()
+        } else {
+            dest.join(rel)
+        };
+        let meta = item.path().symlink_metadata()?;
+        if meta.is_symlink() {
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(fs::read_link(item.path())?, &to)?;
+        } else if meta.is_dir() {
+            fs::create_dir(&to)?;
+            fs::set_permissions(&to, meta.permissions())?;
+        } else {
+            fs::copy(item.path(), &to)?;
+        }
+    }
+    Ok(())
+}
+
+fn move_into_place(src: &Path, dest: &Path) -> io::Result<()> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::rename(src, dest).is_ok() {
+        return Ok(());
+    }
+    copy_tree(src, dest).inspect_err(|_| {
+        let _ = if dest.is_dir() {
+            fs::remove_dir_all(dest)
+        } else {
+            fs::remove_file(dest)
+        };
+    })
+}
+
+/// Put every entry of `archive` back where it came from. The archive is
+/// unpacked next to itself first; an entry whose original location is
+/// taken again, lies outside the home folder and `/Library`, or is one the
+/// [`RemovalPolicy`] would refuse to remove is skipped as `Refused` rather
+/// than written. The archive itself is kept.
+pub fn restore_backup(archive: &Path) -> Result<RestoreReport> {
+    restore_backup_with(archive, &RemovalPolicy::for_current_user())
+}
+
+/// [`restore_backup`] checking originals against `policy` and its home.
+pub(crate) fn restore_backup_with(archive: &Path, policy: &RemovalPolicy) -> Result<RestoreReport> {
+    let manifest = read_manifest(archive)?;
+    let staging = archive.with_file_name(format!(
+        ".{}.restore-{}",
+        archive.file_name().unwrap_or_default().to_string_lossy(),
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&staging);
+    fs::create_dir_all(&staging).with_context(|| format!("Create {}", staging.display()))?;
+
+    let result = unpack(archive, &staging).map(|()| {
+        let mut report = RestoreReport {
+            archive: archive.to_path_buf(),
+            restored: Vec::new(),
+            failed: Vec::new(),
+        };
+        for entry in manifest.entries {
+            let refuse = |reason: String| UninstallFailure {
+                path: entry.original.clone(),
+                error: reason.clone(),
+                kind: FailureKind::Refused { reason },
+            };
+            if !is_archived_path(&entry.archived) || !entry.original.is_absolute() {
+                report
+                    .failed
+                    .push(refuse(format!("Invalid entry {:?}", entry.archived)));
+            } else if !may_restore_to(&entry.original, policy.home()) {
+                report
+                    .failed
+                    .push(refuse("Outside the home folder and /Library".into()));
+            } else if let Err(refusal) = policy.check(&entry.original) {
+                report.failed.push(refuse(refusal.to_string()));
+            } else if fs::symlink_metadata(&entry.original).is_ok() {
+                report.failed.push(refuse("Already exists".into()));
+            } else {
+                match move_into_place(&staging.join(&entry.archived), &entry.original) {
+                    Ok(()) => report.restored.push(entry.original),
+                    Err(e) => report.failed.push(UninstallFailure {
+                        path: entry.original,
+                        error: e.to_string(),
+                        kind: FailureKind::Error,
+                    }),
+                }
+            }
+        }
+        report
+    });
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+/// Whether `original` lies under `home` or `/Library`.
+fn may_restore_to(original: &Path, home: Option<&Path>) -> bool {
+    original.starts_with("/Library") || home.is_some_and(|h| original.starts_with(h))
+}
+
+fn unpack(archive: &Path, into: &Path) -> Result<()> {
+    let file = fs::File::open(archive).with_context(|| format!("Open {}", archive.display()))?;
+    match BackupFormat::of(archive)? {
+        BackupFormat::TarZst => {
+            let mut tar = tar::Archive::new(zstd::Decoder::new(file)?);
+            tar.set_preserve_permissions(true);
+            tar.unpack(into)
+        }
+        BackupFormat::Zip => zip::ZipArchive::new(file)?
+            .extract(into)
+            .map_err(io::Error::other),
+    }
+    .with_context(|| format!("Extract {}", archive.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archives_paths_with_manifest() {
+        let root = std::env::temp_dir().join(format!("backup-test-{}", std::process::id()));
+        let prefs = root.join("Preferences").join("com.foo.plist");
+        let support = root.join("Support").join("Foo");
+        fs::create_dir_all(prefs.parent().unwrap()).unwrap();
+        fs::create_dir_all(support.join("db")).unwrap();
+        fs::write(&prefs, "plist").unwrap();
+        fs::write(support.join("db").join("main.sqlite"), "data").unwrap();
+
+        let archive = create_backup(
+            &root.join("backups"),
+            "Foo / reset",
+            &[prefs.clone(), root.join("gone"), support.clone()],
+            None,
+            BackupFormat::TarZst,
+        )
+        .unwrap();
+        let manifest = read_manifest(&archive).unwrap();
+        let name = archive.file_name().unwrap().to_string_lossy().into_owned();
+        let dir = root.join("backups");
+        let first = create_archive_file(&dir, "Foo", 1, BackupFormat::Zip)
+            .unwrap()
+            .0;
+        let second = create_archive_file(&dir, "Foo", 1, BackupFormat::Zip)
+            .unwrap()
+            .0;
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(name.starts_with("Foo---reset-"), "{name}");
+        assert!(name.ends_with(".tar.zst"));
+        assert_eq!(manifest.label, "Foo / reset");
+        assert_eq!(first, dir.join("Foo-1.zip"));
+        assert_eq!(second, dir.join("Foo-1-2.zip"));
+        assert_eq!(
+            manifest.entries,
+            vec![
+                BackupEntry {
+                    original: prefs,
+                    archived: "files/0/com.foo.plist".into(),
+                },
+                BackupEntry {
+                    original: support,
+                    archived: "files/2/Foo".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn restores_zip_backup_to_original_locations() {
+        let root = std::env::temp_dir().join(format!("restore-test-{}", std::process::id()));
+        let prefs = root.join("Preferences").join("com.foo.plist");
+        let support = root.join("Support").join("Foo");
+        fs::create_dir_all(prefs.parent().unwrap()).unwrap();
+        fs::create_dir_all(support.join("db")).unwrap();
+        fs::write(&prefs, "plist").unwrap();
+        fs::write(support.join("db").join("main.sqlite"), "data").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("db/main.sqlite", support.join("link")).unwrap();
+
+        let archive = create_backup(
+            &root.join("backups"),
+            "Foo",
+            &[prefs.clone(), support.clone()],
+            None,
+            BackupFormat::Zip,
+        )
+        .unwrap();
+        fs::remove_file(&prefs).unwrap();
+        fs::remove_dir_all(&support).unwrap();
+        // Taken again since the backup: left alone.
+        fs::create_dir_all(prefs.parent().unwrap()).unwrap();
+        fs::write(&prefs, "new").unwrap();
+
+        let report = restore_backup_with(&archive, &RemovalPolicy::with_home(&root)).unwrap();
+        let db = fs::read_to_string(support.join("db").join("main.sqlite")).unwrap();
+        let kept = fs::read_to_string(&prefs).unwrap();
+        #[cfg(unix)]
+        let link = fs::read_link(support.join("link")).unwrap();
+        let leftovers = fs::read_dir(root.join("backups")).unwrap().count();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(archive.to_string_lossy().ends_with(".zip"));
+        assert_eq!(report.restored, vec![support]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].path, prefs);
+        assert!(matches!(report.failed[0].kind, FailureKind::Refused { .. }));
+        assert_eq!((db.as_str(), kept.as_str()), ("data", "new"));
+        #[cfg(unix)]
+        assert_eq!(link, Path::new("db/main.sqlite"));
+        assert_eq!(leftovers, 1, "staging folder removed");
+    }
+
+    #[test]
+    fn refuses_restores_outside_home_and_library() {
+        let root = std::env::temp_dir().join(format!("restore-refuse-{}", std::process::id()));
+        let home = root.join("home");
+        let inside = home.join("Documents").join("foo.txt");
+        let outside = root.join("elsewhere").join("foo.txt");
+        let library = home.join("Library").join("Preferences");
+        for path in [&inside, &outside] {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "data").unwrap();
+        }
+        fs::create_dir_all(&library).unwrap();
+
+        let archive = create_backup(
+            &root.join("backups"),
+            "Foo",
+            &[inside.clone(), outside.clone(), library.clone()],
+            None,
+            BackupFormat::TarZst,
+        )
+        .unwrap();
+        fs::remove_dir_all(&home).unwrap();
+        fs::remove_dir_all(outside.parent().unwrap()).unwrap();
+
+        let report = restore_backup_with(&archive, &RemovalPolicy::with_home(&home)).unwrap();
+        let outside_back = outside.exists();
+        let library_back = library.exists();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(report.restored, vec![inside]);
+        let refused: Vec<_> = report.failed.iter().map(|f| f.path.clone()).collect();
+        assert_eq!(refused, vec![outside, library]);
+        assert!(
+            report
+                .failed
+                .iter()
+                .all(|f| matches!(f.kind, FailureKind::Refused { .. }))
+        );
+        assert!(!outside_back && !library_back);
+    }
+
+    #[test]
+    fn rejects_entries_outside_the_archive_tree() {
+        assert!(is_archived_path("files/0/com.foo.plist"));
+        assert!(!is_archived_path("files/0/../../etc"));
+        assert!(!is_archived_path("/etc/passwd"));
+        assert!(!is_archived_path("manifest.json"));
+    }
+}
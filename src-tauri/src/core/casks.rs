@@ -0,0 +1,407 @@
+//! Homebrew cask awareness.
+//!
+//! `brew install --cask` leaves a record per cask under the Caskroom:
+//!
+//! ```text
+//! <caskroom>/<token>/<version>/                          staged artifacts
+//! <caskroom>/<token>/.metadata/<version>/<timestamp>/Casks/<token>.json
+//! ```
+//!
+//! The JSON is the cask definition at install time. Its `artifacts` list
+//! names the `.app` bundles the cask moved into `/Applications` and the
+//! `zap` stanza lists the support files the cask author knows about. Older
+//! installs only have `<token>.rb`; for those we recover the `app` lines and
+//! skip `zap`.
+//!
+//! Removing a cask app by hand leaves the Caskroom entry behind, so `brew`
+//! keeps reporting it as installed. Callers can either delegate to
+//! [`brew_uninstall_zap`] or merge [`zap_paths`] into the related list.
+
+use anyhow::{Context, Result, bail};
+use home::home_dir;
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use super::version::AppVersion;
+
+/// Caskroom locations on Apple silicon and Intel installs.
+pub const CASKROOMS: &[&str] = &["/opt/homebrew/Caskroom", "/usr/local/Caskroom"];
+
+const BREW_BINARIES: &[&str] = &["/opt/homebrew/bin/brew", "/usr/local/bin/brew"];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cask {
+    pub token: String,
+    pub version: Option<String>,
+    /// File names of the `.app` bundles the cask installs (after `target:`
+    /// renames), e.g. `"Visual Studio Code.app"`.
+    pub apps: Vec<String>,
+    /// Raw `zap` `trash`/`delete` entries, possibly with `~` and globs.
+    pub zap: Vec<String>,
+    /// Raw `zap` `rmdir` entries; only removed when empty.
+    pub zap_rmdir: Vec<String>,
+}
+
+/// Sorted non-hidden subdirectory names of `dir`.
+fn subdirs(dir: &Path) -> Vec<String> {
+    let mut v: Vec<String> = fs::read_dir(dir)
+        .map(|rd| {
+            rd.flatten()
+                .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+                .filter_map(|e| e.file_name().to_str().map(str::to_string))
+                .filter(|n| !n.starts_with('.'))
+                .collect()
+        })
+        .unwrap_or_default();
+    v.sort();
+    v
+}
+
+/// Strings in a JSON value that is either a string or an array whose items
+/// may be strings. Non-string items (option hashes) are skipped.
+fn strings(v: &Value) -> Vec<String> {
+    match v {
+        Value::String(s) => vec![s.clone()],
+        Value::Array(a) => a
+            .iter()
+            .filter_map(|x| x.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Fill `cask` from the `artifacts` of a cask JSON definition.
+fn apply_json(cask: &mut Cask, json: &Value) {
+    for artifact in json
+        .get("artifacts")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        if let Some(app) = artifact.get("app").and_then(Value::as_array) {
+            // ["Foo.app"] or ["Foo.app", {"target": "Bar.app"}]
+            let target = app
+                .iter()
+                .find_map(|x| x.get("target").and_then(Value::as_str));
+            let source = app.first().and_then(Value::as_str);
+            if let Some(name) = target.or(source) {
+                let name = Path::new(name)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| name.to_string());
+                cask.apps.push(name);
+            }
+        }
+        for stanza in artifact
+            .get("zap")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            for key in ["trash", "delete"] {
+                if let Some(v) = stanza.get(key) {
+                    cask.zap.extend(strings(v));
+                }
+            }
+            if let Some(v) = stanza.get("rmdir") {
+                cask.zap_rmdir.extend(strings(v));
+            }
+        }
+    }
+}
+
+/// Recover `app "Foo.app"` lines from a Ruby cask definition.
+fn apply_ruby(cask: &mut Cask, source: &str) {
+    for line in source.lines() {
+        let Some(rest) = line.trim().strip_prefix("app ") else {
+            continue;
+        };
+        let mut quoted = rest.split('"').skip(1).step_by(2);
+        let source = quoted.next();
+        let target = rest
+            .split_once("target:")
+            .and_then(|(_, t)| t.split('"').nth(1));
+        if let Some(name) = target.or(source) {
+            cask.apps
+                .push(name.rsplit('/').next().unwrap_or(name).to_string());
+        }
+    }
+}
+
+/// Read one `<caskroom>/<token>` directory. `None` if it has no metadata.
+pub fn read_cask(token_dir: &Path) -> Option<Cask> {
+    let token = token_dir.file_name()?.to_str()?.to_string();
+    let metadata = token_dir.join(".metadata");
+
+    // The installed version is the staged directory; fall back to the
+    // newest metadata version if staging was cleaned up.
+    let mut versions = subdirs(token_dir);
+    if versions.is_empty() {
+        versions = subdirs(&metadata);
+    }
+    let version = versions
+        .into_iter()
+        .max_by(|a, b| AppVersion::parse(a).cmp(&AppVersion::parse(b)))?;
+
+    // Timestamps are `YYYYMMDDhhmmss.mmm`, so the last one is the newest.
+    let stamp = subdirs(&metadata.join(&version)).pop()?;
+    let casks = metadata.join(&version).join(stamp).join("Casks");
+
+    let mut cask = Cask {
+        token: token.clone(),
+        version: Some(version),
+        ..Default::default()
+    };
+    let json = casks.join(format!("{token}.json"));
+    let rb = casks.join(format!("{token}.rb"));
+    if let Ok(raw) = fs::read(&json) {
+        let value: Value = serde_json::from_slice(&raw).ok()?;
+        apply_json(&mut cask, &value);
+    } else if let Ok(raw) = fs::read_to_string(&rb) {
+        apply_ruby(&mut cask, &raw);
+    } else {
+        return None;
+    }
+    Some(cask)
+}
+
+/// Every cask installed under `caskroom`.
+pub fn read_caskroom(caskroom: &Path) -> Vec<Cask> {
+    subdirs(caskroom)
+        .into_iter()
+        .filter_map(|token| read_cask(&caskroom.join(token)))
+        .collect()
+}
+
+/// Every cask in the standard Caskroom locations.
+pub fn scan_casks() -> Vec<Cask> {
+    CASKROOMS
+        .iter()
+        .flat_map(|root| read_caskroom(Path::new(root)))
+        .collect()
+}
+
+/// The cask that installed the bundle at `app_path`, matched by file name.
+pub fn cask_for_app<'a>(casks: &'a [Cask], app_path: &Path) -> Option<&'a Cask> {
+    let name = app_path.file_name()?.to_str()?;
+    casks.iter().find(|c| c.apps.iter().any(|a| a == name))
+}
+
+/// `*`/`?` wildcard match for a single path component. On a mismatch only
+/// the most recent `*` is retried, one byte further along, so the work is
+/// bounded by pattern length times name length however many stars there
+/// are.
+fn wildcard_match(pattern: &[u8], name: &[u8]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the name byte it currently stops before.
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((sp, sn)) => {
+                    star = Some((sp, sn + 1));
+                    p = sp + 1;
+                    n = sn + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// Expand `~` and wildcards in a zap entry into existing paths. Wildcards
+/// are only honoured in the last component, which covers the patterns
+/// casks use in practice (`com.example.app*.plist`).
+pub fn expand_zap_path(entry: &str, home: &Path) -> Vec<PathBuf> {
+    let path = match entry.strip_prefix("~/") {
+        Some(rest) => home.join(rest),
+        None => PathBuf::from(entry),
+    };
+    if !path.is_absolute() {
+        return Vec::new();
+    }
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return Vec::new();
+    };
+    if !name.contains(['*', '?']) {
+        return if path.exists() || path.is_symlink() {
+            vec![path]
+        } else {
+            Vec::new()
+        };
+    }
+    let Some(parent) = path.parent() else {
+        return Vec::new();
+    };
+    let mut out: Vec<PathBuf> = fs::read_dir(parent)
+        .map(|rd| {
+            rd.flatten()
+                .filter(|e| wildcard_match(name.as_bytes(), e.file_name().as_encoded_bytes()))
+                .map(|e| e.path())
+                .collect()
+        })
+        .unwrap_or_default();
+    out.sort();
+    out
+}
+
+/// Existing paths named by the cask's `zap` stanza. `rmdir` entries are
+/// included only when the directory is empty.
+pub fn zap_paths(cask: &Cask) -> Vec<PathBuf> {
+    let Some(home) = home_dir() else {
+        return Vec::new();
+    };
+    let mut out: Vec<PathBuf> = cask
+        .zap
+        .iter()
+        .flat_map(|e| expand_zap_path(e, &home))
+        .collect();
+    out.extend(
+        cask.zap_rmdir
+            .iter()
+            .flat_map(|e| expand_zap_path(e, &home))
+            .filter(|p| fs::read_dir(p).is_ok_and(|mut rd| rd.next().is_none())),
+    );
+    out.sort();
+    out.dedup();
+    out
+}
+
+/// Add the zap paths of the cask `token` (if installed) to `paths`, keeping
+/// the list sorted and free of duplicates.
+pub fn merge_zap_paths(casks: &[Cask], token: Option<&str>, paths: &mut Vec<PathBuf>) {
+    if let Some(c) = token.and_then(|t| casks.iter().find(|c| c.token == t)) {
+        paths.extend(zap_paths(c));
+        paths.sort();
+        paths.dedup();
+    }
+}
+
+/// Path to the `brew` executable, if Homebrew is installed.
+pub fn brew_path() -> Option<PathBuf> {
+    BREW_BINARIES
+        .iter()
+        .map(PathBuf::from)
+        .find(|p| p.is_file())
+}
+
+/// Run `brew uninstall --cask --zap <token>`, which removes the app, the
+/// Caskroom entry and the zap paths in one go.
+pub fn brew_uninstall_zap(token: &str) -> Result<()> {
+    let brew = brew_path().context("Homebrew is not installed")?;
+    let out = Command::new(brew)
+        .args(["uninstall", "--cask", "--zap", "--"])
+        .arg(token)
+        .env("HOMEBREW_NO_AUTO_UPDATE", "1")
+        .output()
+        .context("Failed to run brew")?;
+    if !out.status.success() {
+        bail!(
+            "brew uninstall --zap {token} failed: {}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CASK_JSON: &str = r#"{
+      "token": "example",
+      "version": "1.10.0",
+      "artifacts": [
+        {"uninstall": [{"quit": "com.example.app"}]},
+        {"app": ["Example.app", {"target": "Example Pro.app"}]},
+        {"zap": [{
+          "trash": ["~/Library/Application Support/Example", "~/Library/Preferences/com.example.app*.plist"],
+          "rmdir": "~/Library/Example"
+        }]}
+      ]
+    }"#;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn reads_caskroom_fixture() {
+        let root = std::env::temp_dir().join(format!("caskroom-test-{}", std::process::id()));
+        let example = root.join("example");
+        fs::create_dir_all(example.join("1.9.0")).unwrap();
+        fs::create_dir_all(example.join("1.10.0")).unwrap();
+        write(
+            &example.join(".metadata/1.10.0/20240101120000.000/Casks/example.json"),
+            "{}",
+        );
+        write(
+            &example.join(".metadata/1.10.0/20240301120000.000/Casks/example.json"),
+            CASK_JSON,
+        );
+        write(
+            &root.join("legacy/.metadata/2.0/20200101000000.000/Casks/legacy.rb"),
+            "cask \"legacy\" do\n  version \"2.0\"\n  app \"Legacy.app\"\nend\n",
+        );
+        fs::create_dir_all(root.join("broken/3.0")).unwrap();
+
+        let casks = read_caskroom(&root);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(casks.len(), 2);
+        let example = &casks[0];
+        assert_eq!(example.token, "example");
+        assert_eq!(example.version.as_deref(), Some("1.10.0"));
+        assert_eq!(example.apps, vec!["Example Pro.app"]);
+        assert_eq!(example.zap.len(), 2);
+        assert_eq!(example.zap_rmdir, vec!["~/Library/Example"]);
+        assert_eq!(casks[1].apps, vec!["Legacy.app"]);
+        assert_eq!(
+            cask_for_app(&casks, Path::new("/Applications/Legacy.app")).map(|c| &c.token[..]),
+            Some("legacy")
+        );
+    }
+
+    #[test]
+    fn expands_zap_globs() {
+        let home = std::env::temp_dir().join(format!("zap-test-{}", std::process::id()));
+        let prefs = home.join("Library/Preferences");
+        write(&prefs.join("com.example.app.plist"), "");
+        write(&prefs.join("com.example.app.helper.plist"), "");
+        write(&prefs.join("com.other.plist"), "");
+
+        let globbed = expand_zap_path("~/Library/Preferences/com.example.app*.plist", &home);
+        let missing = expand_zap_path("~/Library/Caches/com.example.app", &home);
+        fs::remove_dir_all(&home).unwrap();
+
+        assert_eq!(
+            globbed,
+            vec![
+                prefs.join("com.example.app.helper.plist"),
+                prefs.join("com.example.app.plist"),
+            ]
+        );
+        assert!(missing.is_empty());
+        assert!(wildcard_match(b"a?c*", b"abcdef"));
+        assert!(!wildcard_match(b"a?c", b"ac"));
+        assert!(wildcard_match(b"*", b""));
+        assert!(wildcard_match(b"a*b*c", b"aXbYbZc"));
+        assert!(!wildcard_match(b"a*b", b"acbd"));
+        let name = [b'a'; 64];
+        assert!(!wildcard_match(b"*a*a*a*a*a*a*a*a*b", &name));
+    }
+}
@@ -0,0 +1,355 @@
+//! Reclaim space from an app's regenerable data without uninstalling it.
+//!
+//! Candidates come from [`find_related_paths`] plus the well-known
+//! per-bundle locations it doesn't walk (`HTTPStorages`, `Saved Application
+//! State`, a container's own `Caches`, crash reports). Each is classified
+//! by where it sits under `~/Library`; anything outside the requested
+//! [`CleanCategory`]s, including Preferences and Application Support, is
+//! left alone.
+
+use home::home_dir;
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use super::{
+    apps::compute_size,
+    error::CoreError,
+    exclusions::Exclusions,
+    plist_info::{PlistInfo, read_info_from_app},
+    progress::{ProgressKind, ProgressSink, Task},
+    related::find_related_paths,
+    running::is_app_running_simple,
+    trash::remove_path,
+    uninstall::failure,
+};
+use crate::models::{CleanCategory, CleanItem, CleanReport, RemovalMode, Settings};
+
+pub const ALL_CATEGORIES: &[CleanCategory] = &[
+    CleanCategory::Caches,
+    CleanCategory::Logs,
+    CleanCategory::HttpStorages,
+    CleanCategory::SavedApplicationState,
+    CleanCategory::DiagnosticReports,
+];
+
+fn names(path: &Path) -> Vec<&str> {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(s) => s.to_str(),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Category of `rest`, a path relative to a `Library` folder. `min_depth`
+/// is 2 for `~/Library` itself, so the shared `Caches` folder never
+/// qualifies, and 1 inside an app's own container.
+fn categorize_library(rest: &[&str], min_depth: usize) -> Option<CleanCategory> {
+    if rest.len() < min_depth {
+        return None;
+    }
+    match rest {
+        ["Caches", ..] => Some(CleanCategory::Caches),
+        ["Logs", "DiagnosticReports"] => None,
+        ["Logs", "DiagnosticReports", ..] => Some(CleanCategory::DiagnosticReports),
+        ["Logs", ..] => Some(CleanCategory::Logs),
+        ["HTTPStorages", ..] => Some(CleanCategory::HttpStorages),
+        ["Saved Application State", ..] => Some(CleanCategory::SavedApplicationState),
+        _ => None,
+    }
+}
+
+/// Which category `path` belongs to, or `None` if it must be kept.
+pub fn categorize(path: &Path, home: &Path) -> Option<CleanCategory> {
+    let rest = path.strip_prefix(home.join("Library")).ok()?;
+    let rest = names(rest);
+    match rest.as_slice() {
+        ["Containers", _, "Data", "Library", inner @ ..] => categorize_library(inner, 1),
+        ["Containers", ..] | ["Group Containers", ..] => None,
+        _ => categorize_library(&rest, 2),
+    }
+}
+
+/// Per-bundle locations `find_related_paths` doesn't cover.
+fn known_paths(home: &Path, info: &PlistInfo, app_name: &str) -> Vec<PathBuf> {
+    let lib = home.join("Library");
+    let mut v = vec![lib.join("Logs").join(app_name)];
+    if let Some(bid) = &info.bundle_id {
+        let data = lib
+            .join("Containers")
+            .join(bid)
+            .join("Data")
+            .join("Library");
+        v.extend([
+            lib.join("Caches").join(bid),
+            lib.join("HTTPStorages").join(bid),
+            lib.join("Saved Application State")
+                .join(format!("{bid}.savedState")),
+            lib.join("Logs").join(bid),
+            data.join("Caches"),
+            data.join("Logs"),
+            data.join("Saved Application State"),
+        ]);
+    }
+
+    // Crash reports are named `<Process>-<date>.ips` or `<Process>_<date>...`.
+    let prefixes: Vec<&str> = [Some(app_name), info.executable.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect();
+    if let Ok(reports) = fs::read_dir(lib.join("Logs").join("DiagnosticReports")) {
+        for e in reports.flatten() {
+            let name = e.file_name();
+            let name = name.to_string_lossy();
+            if prefixes.iter().any(|p| {
+                name.strip_prefix(p)
+                    .is_some_and(|rest| rest.starts_with(['-', '_', '.']))
+            }) {
+                v.push(e.path());
+            }
+        }
+    }
+    v
+}
+
+/// Everything of `categories` (all of them when empty) that belongs to the
+/// app, sized. `related` is the app's [`find_related_paths`] result.
+/// Excluded paths and paths inside another candidate are dropped.
+pub fn plan_clean_in(
+    home: &Path,
+    info: &PlistInfo,
+    app_name: &str,
+    related: Vec<PathBuf>,
+    categories: &[CleanCategory],
+    exclusions: &Exclusions,
+) -> Vec<CleanItem> {
+    let categories = if categories.is_empty() {
+        ALL_CATEGORIES
+    } else {
+        categories
+    };
+    let mut paths = related;
+    paths.extend(known_paths(home, info, app_name));
+    paths.sort();
+    paths.dedup();
+
+    let mut items: Vec<CleanItem> = Vec::new();
+    for path in paths {
+        // Sorted, so an ancestor always comes before its descendants.
+        if items.iter().any(|i| path.starts_with(&i.path)) {
+            continue;
+        }
+        let Some(category) = categorize(&path, home) else {
+            continue;
+        };
+        if !categories.contains(&category)
+            || exclusions.is_excluded(&path)
+            || fs::symlink_metadata(&path).is_err()
+        {
+            continue;
+        }
+        items.push(CleanItem {
+            bytes: compute_size(&path).unwrap_or(0),
+            path,
+            category,
+        });
+    }
+    items.sort_by(|a, b| {
+        a.category
+            .cmp(&b.category)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    items
+}
+
+/// [`plan_clean_in`] for the app at `app_path`, for the current user.
+pub fn plan_clean(
+    app_path: &Path,
+    app_name: &str,
+    categories: &[CleanCategory],
+    max_depth: usize,
+    exclusions: &Exclusions,
+) -> Result<Vec<CleanItem>, CoreError> {
+    let home = home_dir().ok_or_else(|| CoreError::other("No home directory"))?;
+    let info = read_info_from_app(app_path)?;
+    let related = find_related_paths(
+        info.bundle_id.as_deref(),
+        Some(app_name),
+        max_depth,
+        exclusions,
+    );
+    Ok(plan_clean_in(
+        &home, &info, app_name, related, categories, exclusions,
+    ))
+}
+
+/// Remove `items` with `mode`, reporting a `clean_data` task to `sink`.
+/// Per-item errors are recorded and don't stop the run.
+pub fn clean_items(
+    app_path: PathBuf,
+    items: Vec<CleanItem>,
+    mode: RemovalMode,
+    sink: &dyn ProgressSink,
+) -> CleanReport {
+    let task = Task::start(sink, ProgressKind::CleanData, "Cleaning app data...");
+    let mut report = CleanReport {
+        app_path,
+        removed: Vec::new(),
+        failed: Vec::new(),
+        freed_bytes: 0,
+    };
+    let total = items.len();
+    for (i, item) in items.into_iter().enumerate() {
+        let result = remove_path(&item.path, mode);
+        task.set_items(i as u64 + 1, Some(total as u64));
+        match result {
+            Ok(()) => {
+                report.freed_bytes += item.bytes;
+                task.set_bytes(report.freed_bytes);
+                task.progress(
+                    (i + 1) as f32 / total as f32,
+                    format!("Removed {}", item.path.display()),
+                );
+                report.removed.push(item.path);
+            }
+            Err(e) => {
+                task.report(
+                    (i + 1) as f32 / total as f32,
+                    format!("Failed to remove {}: {e}", item.path.display()),
+                    Some(e.to_string()),
+                );
+                report.failed.push(failure(item.path, &e));
+            }
+        }
+    }
+    task.finish(format!("Cleaned {} item(s).", report.removed.len()));
+    report
+}
+
+/// Plan and remove `categories` of the app's data. Refuses while the app
+/// runs, since it may be writing to its caches.
+pub fn clean_app_data(
+    app_path: PathBuf,
+    app_name: &str,
+    categories: &[CleanCategory],
+    settings: &Settings,
+    exclusions: &Exclusions,
+    sink: &dyn ProgressSink,
+) -> Result<CleanReport, CoreError> {
+    let items = plan_clean(
+        &app_path,
+        app_name,
+        categories,
+        settings.related_max_depth,
+        exclusions,
+    )?;
+    let bundle_id = read_info_from_app(&app_path)?.bundle_id;
+    if is_app_running_simple(Some(&app_path), bundle_id.as_deref(), Some(app_name)) {
+        return Err(CoreError::AppRunning {
+            name: app_name.to_string(),
+            path: Some(app_path),
+        });
+    }
+    Ok(clean_items(app_path, items, settings.removal_mode, sink))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::progress::NullSink;
+
+    #[test]
+    fn categorizes_by_library_location() {
+        let home = Path::new("/Users/me");
+        let lib = home.join("Library");
+        let cat = |rel: &str| categorize(&lib.join(rel), home);
+        assert_eq!(cat("Caches/com.foo"), Some(CleanCategory::Caches));
+        assert_eq!(cat("Caches"), None);
+        assert_eq!(cat("Logs/Foo"), Some(CleanCategory::Logs));
+        assert_eq!(cat("Logs/DiagnosticReports"), None);
+        assert_eq!(
+            cat("Logs/DiagnosticReports/Foo-2025.ips"),
+            Some(CleanCategory::DiagnosticReports)
+        );
+        assert_eq!(
+            cat("HTTPStorages/com.foo"),
+            Some(CleanCategory::HttpStorages)
+        );
+        assert_eq!(
+            cat("Containers/com.foo/Data/Library/Caches"),
+            Some(CleanCategory::Caches)
+        );
+        assert_eq!(cat("Containers/com.foo"), None);
+        assert_eq!(cat("Containers/com.foo/Data/Library/Preferences"), None);
+        assert_eq!(cat("Preferences/com.foo.plist"), None);
+        assert_eq!(cat("Application Support/Foo"), None);
+    }
+
+    #[test]
+    fn plans_and_cleans_only_requested_categories() {
+        let home = std::env::temp_dir().join(format!("clean-test-{}", std::process::id()));
+        let lib = home.join("Library");
+        let write = |rel: &str, len: usize| {
+            let p = lib.join(rel);
+            fs::create_dir_all(p.parent().unwrap()).unwrap();
+            fs::write(p, vec![0u8; len]).unwrap();
+        };
+        write("Caches/com.foo/Cache.db", 100);
+        write("Caches/com.foo/fsCachedData/1", 10);
+        write("HTTPStorages/com.foo/httpstorages.sqlite", 20);
+        write("Logs/DiagnosticReports/FooHelper_2025-01-01.ips", 5);
+        write("Logs/DiagnosticReports/Foobar-2025-01-01.ips", 5);
+        write("Logs/Foo/foo.log", 7);
+        write("Preferences/com.foo.plist", 1);
+        write("Application Support/Foo/db", 1);
+        let info = PlistInfo {
+            bundle_id: Some("com.foo".into()),
+            executable: Some("FooHelper".into()),
+            ..Default::default()
+        };
+        let related = vec![
+            lib.join("Caches").join("com.foo"),
+            lib.join("Preferences").join("com.foo.plist"),
+            lib.join("Application Support").join("Foo"),
+        ];
+        let none = Exclusions::default();
+
+        let all = plan_clean_in(&home, &info, "Foo", related.clone(), &[], &none);
+        let caches = plan_clean_in(
+            &home,
+            &info,
+            "Foo",
+            related,
+            &[CleanCategory::Caches],
+            &none,
+        );
+        let report = clean_items(
+            home.join("Foo.app"),
+            caches.clone(),
+            RemovalMode::Delete,
+            &NullSink,
+        );
+        let prefs_kept = lib.join("Preferences").join("com.foo.plist").exists();
+        let logs_kept = lib.join("Logs").join("Foo").exists();
+        fs::remove_dir_all(&home).unwrap();
+
+        let planned: Vec<(CleanCategory, u64)> =
+            all.iter().map(|i| (i.category, i.bytes)).collect();
+        assert_eq!(
+            planned,
+            vec![
+                (CleanCategory::Caches, 110),
+                (CleanCategory::Logs, 7),
+                (CleanCategory::HttpStorages, 20),
+                (CleanCategory::DiagnosticReports, 5),
+            ]
+        );
+        assert_eq!(caches.len(), 1);
+        assert_eq!(report.removed, vec![lib.join("Caches").join("com.foo")]);
+        assert_eq!(report.freed_bytes, 110);
+        assert!(report.failed.is_empty());
+        assert!(prefs_kept && logs_kept);
+    }
+}
@@ -0,0 +1,604 @@
+//! Inspect the code signature of an app bundle.
+//!
+//! A Mach-O's `LC_CODE_SIGNATURE` points at a "superblob": an index of typed
+//! blobs (code directory, requirements, entitlements, CMS signature). The
+//! code directory carries the signing identifier, Team ID and flags; the
+//! CMS blob is a PKCS#7 `SignedData` whose certificates give the authority
+//! chain `codesign -dvv` prints as `Authority=...`. Everything is decoded
+//! from bytes, nothing here verifies the signature.
+
+use anyhow::{Context, Result};
+use plist::Value;
+use std::path::Path;
+
+use super::{macho, plist_info::read_info_from_app};
+use crate::models::{AppSecurityInfo, SignerKind};
+
+const CSMAGIC_EMBEDDED_SIGNATURE: u32 = 0xfade_0cc0;
+const CSMAGIC_CODEDIRECTORY: u32 = 0xfade_0c02;
+const CSMAGIC_EMBEDDED_ENTITLEMENTS: u32 = 0xfade_7171;
+const CSMAGIC_BLOBWRAPPER: u32 = 0xfade_0b01;
+const CSSLOT_CODEDIRECTORY: u32 = 0;
+const CSSLOT_ENTITLEMENTS: u32 = 5;
+const CSSLOT_SIGNATURESLOT: u32 = 0x10000;
+
+/// Code directory versions that introduced the fields we read.
+const CD_VERSION_TEAM_ID: u32 = 0x20200;
+const CD_VERSION_RUNTIME: u32 = 0x20500;
+
+pub const CS_ADHOC: u32 = 0x0000_0002;
+pub const CS_HARD: u32 = 0x0000_0100;
+pub const CS_KILL: u32 = 0x0000_0200;
+pub const CS_RESTRICT: u32 = 0x0000_0800;
+pub const CS_LIBRARY_VALIDATION: u32 = 0x0000_2000;
+pub const CS_RUNTIME: u32 = 0x0001_0000;
+pub const CS_LINKER_SIGNED: u32 = 0x0002_0000;
+
+/// Leaf authorities Apple uses for its own software: platform binaries and
+/// App Store builds of Apple apps.
+const APPLE_LEAF_AUTHORITIES: &[&str] = &["Software Signing", "Apple Mac OS Application Signing"];
+const APPLE_ROOT_AUTHORITY: &str = "Apple Root CA";
+
+fn be32(b: &[u8], at: usize) -> Option<u32> {
+    b.get(at..at + 4)
+        .map(|s| u32::from_be_bytes([s[0], s[1], s[2], s[3]]))
+}
+
+/// Iterate `(slot type, blob)` pairs of a superblob. Each blob includes its
+/// own 8-byte magic/length header.
+pub(crate) fn superblob_entries(sb: &[u8]) -> Vec<(u32, &[u8])> {
+    let mut out = Vec::new();
+    if be32(sb, 0) != Some(CSMAGIC_EMBEDDED_SIGNATURE) {
+        return out;
+    }
+    let count = be32(sb, 8).unwrap_or(0) as usize;
+    for i in 0..count {
+        let (Some(kind), Some(off)) = (be32(sb, 12 + i * 8), be32(sb, 16 + i * 8)) else {
+            break;
+        };
+        let off = off as usize;
+        let Some(len) = be32(sb, off + 4) else {
+            continue;
+        };
+        if let Some(blob) = sb.get(off..off + len as usize) {
+            out.push((kind, blob));
+        }
+    }
+    out
+}
+
+/// Payload (after the 8-byte header) of the blob in `slot` with `magic`.
+fn blob_payload(superblob: &[u8], slot: u32, magic: u32) -> Option<&[u8]> {
+    superblob_entries(superblob)
+        .into_iter()
+        .find(|(kind, blob)| *kind == slot && be32(blob, 0) == Some(magic))
+        .and_then(|(_, blob)| blob.get(8..))
+}
+
+/// Certificates of a superblob's CMS signature, in the order they appear.
+fn signature_certificates(superblob: &[u8]) -> Vec<CertNames> {
+    blob_payload(superblob, CSSLOT_SIGNATURESLOT, CSMAGIC_BLOBWRAPPER)
+        .map(cms_certificates)
+        .unwrap_or_default()
+}
+
+/// Authority chain (leaf first, root last) from a superblob. Empty for
+/// ad-hoc signatures, which carry no CMS blob.
+pub fn signing_authorities(superblob: &[u8]) -> Vec<String> {
+    order_chain(signature_certificates(superblob))
+}
+
+/// The fields of a `CS_CodeDirectory` the inspector reports.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CodeDirectory {
+    pub version: u32,
+    pub flags: u32,
+    pub identifier: Option<String>,
+    pub team_id: Option<String>,
+    /// Minimum SDK/runtime version the hardened runtime was built against,
+    /// packed as `xxxx.yy.zz`.
+    pub runtime: Option<u32>,
+}
+
+fn c_string_at(b: &[u8], at: usize) -> Option<String> {
+    let bytes = b.get(at..)?;
+    let end = bytes.iter().position(|&c| c == 0)?;
+    Some(String::from_utf8_lossy(&bytes[..end]).into_owned())
+}
+
+/// Parse the primary code directory (slot 0) of a superblob.
+pub fn code_directory(superblob: &[u8]) -> Option<CodeDirectory> {
+    let (_, cd) = superblob_entries(superblob)
+        .into_iter()
+        .find(|(kind, blob)| {
+            *kind == CSSLOT_CODEDIRECTORY && be32(blob, 0) == Some(CSMAGIC_CODEDIRECTORY)
+        })?;
+    let version = be32(cd, 8)?;
+    let flags = be32(cd, 12)?;
+    let identifier = be32(cd, 20).and_then(|off| c_string_at(cd, off as usize));
+    let team_id = if version >= CD_VERSION_TEAM_ID {
+        be32(cd, 48)
+            .filter(|off| *off != 0)
+            .and_then(|off| c_string_at(cd, off as usize))
+    } else {
+        None
+    };
+    let runtime = if version >= CD_VERSION_RUNTIME {
+        be32(cd, 88)
+    } else {
+        None
+    };
+    Some(CodeDirectory {
+        version,
+        flags,
+        identifier,
+        team_id,
+        runtime,
+    })
+}
+
+/// Embedded entitlements (the XML plist in slot 5), if any.
+pub fn entitlements(superblob: &[u8]) -> Option<Value> {
+    let xml = blob_payload(
+        superblob,
+        CSSLOT_ENTITLEMENTS,
+        CSMAGIC_EMBEDDED_ENTITLEMENTS,
+    )?;
+    Value::from_reader_xml(xml).ok()
+}
+
+fn signer_kind(flags: u32, authorities: &[String]) -> SignerKind {
+    let Some(leaf) = authorities.first() else {
+        return if flags & (CS_ADHOC | CS_LINKER_SIGNED) != 0 {
+            SignerKind::AdHoc
+        } else {
+            SignerKind::Unknown
+        };
+    };
+    if leaf == "Software Signing" {
+        SignerKind::Apple
+    } else if leaf == "Apple Mac OS Application Signing" {
+        SignerKind::AppStore
+    } else if leaf.starts_with("Developer ID Application:") {
+        SignerKind::DeveloperId
+    } else if leaf.starts_with("Apple Development:") || leaf.starts_with("Mac Developer:") {
+        SignerKind::Development
+    } else {
+        SignerKind::Unknown
+    }
+}
+
+/// Decode everything the inspector reports from a superblob.
+pub fn security_info_from_superblob(superblob: &[u8]) -> AppSecurityInfo {
+    let cd = code_directory(superblob).unwrap_or_default();
+    let certs = signature_certificates(superblob);
+    // The leaf certificate's OU is the Team ID; older code directories
+    // don't carry it themselves.
+    let leaf_ou = order_chain(certs.clone()).first().and_then(|leaf| {
+        certs
+            .iter()
+            .find(|c| c.subject_cn.as_ref() == Some(leaf))
+            .and_then(|c| c.subject_ou.clone())
+    });
+    let authorities = order_chain(certs);
+    AppSecurityInfo {
+        signed: true,
+        signer: signer_kind(cd.flags, &authorities),
+        identifier: cd.identifier,
+        team_id: cd.team_id.or(leaf_ou),
+        authorities,
+        flags: cd.flags,
+        hardened_runtime: cd.flags & CS_RUNTIME != 0,
+        entitlements: entitlements(superblob).and_then(|v| serde_json::to_value(v).ok()),
+        sealed_resources: None,
+        notarization_stapled: false,
+    }
+}
+
+/// Number of files sealed by `Contents/_CodeSignature/CodeResources`
+/// (`files2`, falling back to the legacy `files` dictionary).
+fn sealed_resource_count(app_path: &Path) -> Option<usize> {
+    let path = app_path
+        .join("Contents")
+        .join("_CodeSignature")
+        .join("CodeResources");
+    let v = Value::from_file(path).ok()?;
+    let dict = v.as_dictionary()?;
+    dict.get("files2")
+        .or_else(|| dict.get("files"))
+        .and_then(|f| f.as_dictionary())
+        .map(|f| f.len())
+}
+
+/// Inspect the code signature of the `.app` bundle at `app_path`: the main
+/// executable's embedded signature, the sealed resources, and whether a
+/// notarization ticket is stapled (`stapler` writes it to
+/// `Contents/CodeResources`).
+pub fn inspect_app_security(app_path: &Path) -> Result<AppSecurityInfo> {
+    let exe = read_info_from_app(app_path)?
+        .executable
+        .context("Info.plist has no CFBundleExecutable")?;
+    let exe_path = app_path.join("Contents").join("MacOS").join(exe);
+    let superblob = macho::read_code_signature(&exe_path)
+        .with_context(|| format!("Read code signature of {}", exe_path.display()))?;
+
+    let mut info = match superblob {
+        Some(sb) => security_info_from_superblob(&sb),
+        None => AppSecurityInfo::default(),
+    };
+    info.sealed_resources = sealed_resource_count(app_path);
+    info.notarization_stapled = app_path.join("Contents").join("CodeResources").is_file();
+    Ok(info)
+}
+
+/// Read the authority chain of the Mach-O at `executable`. `Ok(None)` means
+/// the binary is unsigned.
+pub fn read_signing_authorities(executable: &Path) -> Result<Option<Vec<String>>> {
+    Ok(macho::read_code_signature(executable)?.map(|sb| signing_authorities(&sb)))
+}
+
+/// True if the chain is one Apple uses to sign its own apps.
+pub fn is_apple_signed(authorities: &[String]) -> bool {
+    match (authorities.first(), authorities.last()) {
+        (Some(leaf), Some(root)) => {
+            APPLE_LEAF_AUTHORITIES.contains(&leaf.as_str()) && root == APPLE_ROOT_AUTHORITY
+        }
+        _ => false,
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct CertNames {
+    pub subject_cn: Option<String>,
+    pub subject_ou: Option<String>,
+    pub issuer_cn: Option<String>,
+}
+
+/// Order certificates leaf → root by following issuer names. Falls back to
+/// the order they appear in the CMS when the names don't link up.
+fn order_chain(certs: Vec<CertNames>) -> Vec<String> {
+    let is_issuer_of_other = |c: &CertNames| {
+        certs
+            .iter()
+            .any(|o| o != c && o.issuer_cn.is_some() && o.issuer_cn == c.subject_cn)
+    };
+    let Some(leaf) = certs.iter().find(|c| !is_issuer_of_other(c)) else {
+        return certs.into_iter().filter_map(|c| c.subject_cn).collect();
+    };
+
+    let mut chain = Vec::new();
+    let mut cur = Some(leaf);
+    while let Some(c) = cur {
+        let Some(ref cn) = c.subject_cn else { break };
+        if chain.contains(cn) {
+            break;
+        }
+        chain.push(cn.clone());
+        cur = certs
+            .iter()
+            .find(|o| o.subject_cn.is_some() && o.subject_cn == c.issuer_cn && *o != c);
+    }
+    chain
+}
+
+/// How deep indefinite-length elements may nest. Real signatures use a
+/// handful of levels; the limit keeps crafted input off the stack.
+const MAX_TLV_DEPTH: usize = 32;
+
+/// One BER/DER TLV: `(tag, contents, rest)`. Supports the indefinite-length
+/// form Apple's CMS encoder uses for the outer structures.
+fn tlv(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    tlv_nested(input, 0)
+}
+
+/// [`tlv`] for an element `depth` indefinite-length elements down. `None`
+/// past [`MAX_TLV_DEPTH`].
+fn tlv_nested(input: &[u8], depth: usize) -> Option<(u8, &[u8], &[u8])> {
+    if depth > MAX_TLV_DEPTH {
+        return None;
+    }
+    let tag = *input.first()?;
+    let first = *input.get(1)?;
+    let body = &input[2..];
+    if first == 0x80 {
+        // Indefinite length: contents run until the 00 00 end marker.
+        let mut rest = body;
+        loop {
+            if rest.len() >= 2 && rest[0] == 0 && rest[1] == 0 {
+                let used = body.len() - rest.len();
+                return Some((tag, &body[..used], &rest[2..]));
+            }
+            let (_, _, r) = tlv_nested(rest, depth + 1)?;
+            rest = r;
+        }
+    }
+    let (len, body) = if first < 0x80 {
+        (first as usize, body)
+    } else {
+        let n = (first & 0x7f) as usize;
+        if n == 0 || n > 4 || body.len() < n {
+            return None;
+        }
+        let len = body[..n]
+            .iter()
+            .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+        (len, &body[n..])
+    };
+    if body.len() < len {
+        return None;
+    }
+    Some((tag, &body[..len], &body[len..]))
+}
+
+/// Children of a constructed value.
+fn children(mut input: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut out = Vec::new();
+    while let Some((tag, body, rest)) = tlv(input) {
+        out.push((tag, body));
+        input = rest;
+    }
+    out
+}
+
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+const TAG_OID: u8 = 0x06;
+const TAG_CONTEXT_0: u8 = 0xa0;
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+const OID_ORG_UNIT: &[u8] = &[0x55, 0x04, 0x0b];
+
+/// Certificates of a CMS `ContentInfo { contentType, [0] SignedData }`.
+pub(crate) fn cms_certificates(der: &[u8]) -> Vec<CertNames> {
+    let Some((TAG_SEQUENCE, content_info, _)) = tlv(der) else {
+        return Vec::new();
+    };
+    let Some(&(TAG_CONTEXT_0, explicit)) = children(content_info).get(1) else {
+        return Vec::new();
+    };
+    let Some((TAG_SEQUENCE, signed_data, _)) = tlv(explicit) else {
+        return Vec::new();
+    };
+    // SignedData: version, digestAlgorithms, encapContentInfo,
+    // [0] certificates, ...
+    children(signed_data)
+        .into_iter()
+        .find(|(tag, _)| *tag == TAG_CONTEXT_0)
+        .map(|(_, certs)| {
+            children(certs)
+                .into_iter()
+                .filter(|(tag, _)| *tag == TAG_SEQUENCE)
+                .filter_map(|(_, cert)| certificate_names(cert))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn certificate_names(cert: &[u8]) -> Option<CertNames> {
+    let (TAG_SEQUENCE, tbs, _) = tlv(cert)? else {
+        return None;
+    };
+    let mut fields = children(tbs);
+    if fields.first().map(|(t, _)| *t) == Some(TAG_CONTEXT_0) {
+        fields.remove(0);
+    }
+    // serialNumber, signature, issuer, validity, subject, ...
+    let issuer = fields.get(2)?.1;
+    let subject = fields.get(4)?.1;
+    Some(CertNames {
+        subject_cn: name_attribute(subject, OID_COMMON_NAME),
+        subject_ou: name_attribute(subject, OID_ORG_UNIT),
+        issuer_cn: name_attribute(issuer, OID_COMMON_NAME),
+    })
+}
+
+/// First attribute with the given OID in an X.501 `Name`.
+fn name_attribute(name: &[u8], oid: &[u8]) -> Option<String> {
+    children(name)
+        .into_iter()
+        .filter(|(tag, _)| *tag == TAG_SET)
+        .flat_map(|(_, set)| children(set))
+        .filter(|(tag, _)| *tag == TAG_SEQUENCE)
+        .find_map(|(_, atv)| {
+            let parts = children(atv);
+            match parts.as_slice() {
+                [(TAG_OID, o), (_, value), ..] if *o == oid => {
+                    Some(String::from_utf8_lossy(value).into_owned())
+                }
+                _ => None,
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn der(tag: u8, body: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        if body.len() < 0x80 {
+            out.push(body.len() as u8);
+        } else {
+            out.push(0x82);
+            out.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        }
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn name(cn: &str) -> Vec<u8> {
+        let mut atv = der(TAG_OID, OID_COMMON_NAME);
+        atv.extend(der(0x0c, cn.as_bytes()));
+        der(TAG_SEQUENCE, &der(TAG_SET, &der(TAG_SEQUENCE, &atv)))
+    }
+
+    fn cert(subject: &str, issuer: &str) -> Vec<u8> {
+        let mut tbs = der(TAG_CONTEXT_0, &der(0x02, &[2]));
+        tbs.extend(der(0x02, &[1]));
+        tbs.extend(der(TAG_SEQUENCE, &[]));
+        tbs.extend(name(issuer));
+        tbs.extend(der(TAG_SEQUENCE, &[]));
+        tbs.extend(name(subject));
+        der(TAG_SEQUENCE, &der(TAG_SEQUENCE, &tbs))
+    }
+
+    /// CMS `ContentInfo` whose outer layers use the indefinite-length form,
+    /// like the ones `codesign` writes.
+    fn cms(certs: &[Vec<u8>]) -> Vec<u8> {
+        let mut signed = der(0x02, &[1]);
+        signed.extend(der(TAG_SET, &[]));
+        signed.extend(der(TAG_SEQUENCE, &[]));
+        signed.extend(der(TAG_CONTEXT_0, &certs.concat()));
+        let mut content = der(TAG_OID, &[0x2a, 0x86, 0x48]);
+        content.extend([TAG_CONTEXT_0, 0x80]);
+        content.extend(der(TAG_SEQUENCE, &signed));
+        content.extend([0, 0]);
+        let mut out = vec![TAG_SEQUENCE, 0x80];
+        out.extend(content);
+        out.extend([0, 0]);
+        out
+    }
+
+    fn blob(magic: u32, payload: &[u8]) -> Vec<u8> {
+        let mut b = magic.to_be_bytes().to_vec();
+        b.extend(((payload.len() + 8) as u32).to_be_bytes());
+        b.extend_from_slice(payload);
+        b
+    }
+
+    fn superblob(blobs: &[(u32, Vec<u8>)]) -> Vec<u8> {
+        let header = 12 + 8 * blobs.len();
+        let total = header + blobs.iter().map(|(_, b)| b.len()).sum::<usize>();
+        let mut sb = CSMAGIC_EMBEDDED_SIGNATURE.to_be_bytes().to_vec();
+        sb.extend((total as u32).to_be_bytes());
+        sb.extend((blobs.len() as u32).to_be_bytes());
+        let mut off = header;
+        for (slot, b) in blobs {
+            sb.extend(slot.to_be_bytes());
+            sb.extend((off as u32).to_be_bytes());
+            off += b.len();
+        }
+        for (_, b) in blobs {
+            sb.extend_from_slice(b);
+        }
+        sb
+    }
+
+    fn signature(certs: &[Vec<u8>]) -> (u32, Vec<u8>) {
+        (CSSLOT_SIGNATURESLOT, blob(CSMAGIC_BLOBWRAPPER, &cms(certs)))
+    }
+
+    /// Version 0x20500 code directory with identifier and Team ID strings
+    /// placed right after the fixed header.
+    fn code_dir(flags: u32, ident: &str, team: &str) -> (u32, Vec<u8>) {
+        let fixed = 96;
+        let ident_off = fixed + 8;
+        let team_off = ident_off + ident.len() + 1;
+        let mut cd = vec![0u8; fixed];
+        cd[0..4].copy_from_slice(&0x20500u32.to_be_bytes());
+        cd[4..8].copy_from_slice(&flags.to_be_bytes());
+        cd[12..16].copy_from_slice(&(ident_off as u32).to_be_bytes());
+        cd[40..44].copy_from_slice(&(team_off as u32).to_be_bytes());
+        cd[80..84].copy_from_slice(&0x000e_0000u32.to_be_bytes());
+        cd.extend_from_slice(ident.as_bytes());
+        cd.push(0);
+        cd.extend_from_slice(team.as_bytes());
+        cd.push(0);
+        (CSSLOT_CODEDIRECTORY, blob(CSMAGIC_CODEDIRECTORY, &cd))
+    }
+
+    #[test]
+    fn extracts_authority_chain_leaf_first() {
+        // Root listed first on purpose: the chain must be ordered by issuer.
+        let sb = superblob(&[signature(&[
+            cert("Apple Root CA", "Apple Root CA"),
+            cert(
+                "Software Signing",
+                "Apple Code Signing Certification Authority",
+            ),
+            cert(
+                "Apple Code Signing Certification Authority",
+                "Apple Root CA",
+            ),
+        ])]);
+        let chain = signing_authorities(&sb);
+        assert_eq!(
+            chain,
+            vec![
+                "Software Signing",
+                "Apple Code Signing Certification Authority",
+                "Apple Root CA",
+            ]
+        );
+        assert!(is_apple_signed(&chain));
+    }
+
+    #[test]
+    fn developer_id_is_not_apple_signed() {
+        let sb = superblob(&[signature(&[
+            cert(
+                "Developer ID Application: Example Corp (ABCDE12345)",
+                "Developer ID Certification Authority",
+            ),
+            cert("Developer ID Certification Authority", "Apple Root CA"),
+            cert("Apple Root CA", "Apple Root CA"),
+        ])]);
+        let chain = signing_authorities(&sb);
+        assert_eq!(chain.len(), 3);
+        assert!(!is_apple_signed(&chain));
+    }
+
+    #[test]
+    fn decodes_code_directory_and_entitlements() {
+        let ents = br#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0"><dict>
+<key>com.apple.security.app-sandbox</key><true/>
+</dict></plist>"#;
+        let sb = superblob(&[
+            code_dir(CS_RUNTIME, "com.example.foo", "ABCDE12345"),
+            (
+                CSSLOT_ENTITLEMENTS,
+                blob(CSMAGIC_EMBEDDED_ENTITLEMENTS, ents),
+            ),
+            signature(&[
+                cert(
+                    "Developer ID Application: Example Corp (ABCDE12345)",
+                    "Developer ID Certification Authority",
+                ),
+                cert("Developer ID Certification Authority", "Apple Root CA"),
+            ]),
+        ]);
+
+        let cd = code_directory(&sb).unwrap();
+        assert_eq!(cd.identifier.as_deref(), Some("com.example.foo"));
+        assert_eq!(cd.team_id.as_deref(), Some("ABCDE12345"));
+        assert_eq!(cd.runtime, Some(0x000e_0000));
+
+        let info = security_info_from_superblob(&sb);
+        assert_eq!(info.signer, SignerKind::DeveloperId);
+        assert!(info.hardened_runtime);
+        assert_eq!(
+            info.entitlements.unwrap()["com.apple.security.app-sandbox"],
+            serde_json::Value::Bool(true)
+        );
+    }
+
+    #[test]
+    fn ad_hoc_signature_has_no_authorities() {
+        let sb = superblob(&[code_dir(CS_ADHOC | CS_LINKER_SIGNED, "a.out", "")]);
+        let info = security_info_from_superblob(&sb);
+        assert_eq!(info.signer, SignerKind::AdHoc);
+        assert!(info.authorities.is_empty());
+        assert!(!info.hardened_runtime);
+    }
+
+    #[test]
+    fn deeply_nested_indefinite_lengths_are_rejected() {
+        let deep = [TAG_SEQUENCE, 0x80].repeat(1_000_000);
+        assert_eq!(tlv(&deep), None);
+        assert!(cms_certificates(&deep).is_empty());
+
+        let mut shallow = [TAG_SEQUENCE, 0x80].repeat(MAX_TLV_DEPTH);
+        shallow.extend([0, 0].repeat(MAX_TLV_DEPTH));
+        assert!(tlv(&shallow).is_some());
+    }
+}
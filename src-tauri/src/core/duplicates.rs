@@ -0,0 +1,221 @@
+//! Find apps installed more than once.
+//!
+//! Two entries belong to the same group when they share a bundle id (e.g.
+//! the same app in `/Applications` and `~/Applications`) or when their
+//! names match after stripping channel suffixes (`Xcode.app` vs
+//! `Xcode-beta.app`). Within a group the highest version is kept as the
+//! "newest"; the others are reported as redundant along with their size.
+
+use std::{cmp::Ordering, collections::HashMap};
+
+use super::{apps::compute_size, version::compare_bundle_versions};
+use crate::models::{AppInfo, DuplicateEntry, DuplicateGroup};
+
+/// Suffixes that mark a release channel or a Finder copy rather than a
+/// different app.
+const CHANNEL_SUFFIXES: &[&str] = &[
+    "beta", "preview", "canary", "dev", "nightly", "insiders", "copy",
+];
+
+/// Lowercased app name with channel suffixes, copy counters and separators
+/// removed: "Xcode-beta" → "xcode", "Foo 2" → "foo", "Bar (1)" → "bar".
+pub fn normalized_name(name: &str) -> String {
+    let is_sep = |c: char| matches!(c, ' ' | '-' | '_');
+    let mut s = name.trim().to_lowercase();
+    if let Some(stripped) = s.strip_suffix(".app") {
+        s = stripped.to_string();
+    }
+    loop {
+        let before = s.len();
+        if s.ends_with(')') {
+            if let Some(open) = s.rfind('(') {
+                s.truncate(open);
+            }
+        }
+        let without_digits = s.trim_end_matches(|c: char| c.is_ascii_digit());
+        if without_digits.len() < s.len() && without_digits.ends_with(is_sep) {
+            s = without_digits.to_string();
+        }
+        for suffix in CHANNEL_SUFFIXES {
+            if let Some(stripped) = s.strip_suffix(suffix) {
+                if stripped.ends_with(is_sep) {
+                    s = stripped.to_string();
+                }
+            }
+        }
+        s = s.trim_end_matches(is_sep).to_string();
+        if s.len() == before {
+            break;
+        }
+    }
+    if s.is_empty() { name.to_lowercase() } else { s }
+}
+
+fn find(parent: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parent[root] != root {
+        root = parent[root];
+    }
+    parent[i] = root;
+    root
+}
+
+/// Cluster `apps` by bundle id and normalized name. Returns index groups
+/// with more than one member, each in input order.
+pub fn group_duplicates(apps: &[AppInfo]) -> Vec<Vec<usize>> {
+    let mut parent: Vec<usize> = (0..apps.len()).collect();
+    let mut by_key: HashMap<String, usize> = HashMap::new();
+
+    for (i, app) in apps.iter().enumerate() {
+        let mut keys = vec![format!("name:{}", normalized_name(&app.name))];
+        if let Some(ref bid) = app.bundle_id {
+            keys.push(format!("bid:{}", bid.to_lowercase()));
+        }
+        for key in keys {
+            match by_key.get(&key) {
+                Some(&j) => {
+                    let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+                    parent[a] = b;
+                }
+                None => {
+                    by_key.insert(key, i);
+                }
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..apps.len() {
+        let root = find(&mut parent, i);
+        groups.entry(root).or_default().push(i);
+    }
+    let mut out: Vec<Vec<usize>> = groups.into_values().filter(|g| g.len() > 1).collect();
+    out.sort_by_key(|g| g[0]);
+    out
+}
+
+/// Index (into `members`) of the newest app: highest version, then most
+/// recently modified.
+fn newest(apps: &[AppInfo], members: &[usize]) -> usize {
+    let mut best = 0;
+    for (k, &i) in members.iter().enumerate().skip(1) {
+        let (a, b) = (&apps[i], &apps[members[best]]);
+        let ord = compare_bundle_versions(
+            (a.version.as_deref(), a.build_version.as_deref()),
+            (b.version.as_deref(), b.build_version.as_deref()),
+        )
+        .then(a.modified_at.cmp(&b.modified_at));
+        if ord == Ordering::Greater {
+            best = k;
+        }
+    }
+    best
+}
+
+/// Group duplicate installs and size the redundant copies. Sizing walks
+/// each redundant bundle, so this is for the explicit duplicates view, not
+/// the regular scan.
+pub fn find_duplicate_apps(apps: &[AppInfo]) -> Vec<DuplicateGroup> {
+    group_duplicates(apps)
+        .into_iter()
+        .map(|members| {
+            let keep = newest(apps, &members);
+            let entries: Vec<DuplicateEntry> = members
+                .iter()
+                .enumerate()
+                .map(|(k, &i)| {
+                    let app = apps[i].clone();
+                    let is_newest = k == keep;
+                    let size = if is_newest {
+                        None
+                    } else {
+                        compute_size(&app.path)
+                    };
+                    DuplicateEntry {
+                        app,
+                        newest: is_newest,
+                        size,
+                    }
+                })
+                .collect();
+            let key = apps[members[keep]]
+                .bundle_id
+                .clone()
+                .unwrap_or_else(|| normalized_name(&apps[members[keep]].name));
+            DuplicateGroup {
+                key,
+                redundant_bytes: entries.iter().filter_map(|e| e.size).sum(),
+                apps: entries,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn app(path: &str, name: &str, bid: Option<&str>, version: Option<&str>) -> AppInfo {
+        AppInfo {
+            path: PathBuf::from(path),
+            name: name.to_string(),
+            bundle_id: bid.map(str::to_string),
+            version: version.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn normalizes_channel_suffixes_and_copies() {
+        assert_eq!(normalized_name("Xcode-beta"), "xcode");
+        assert_eq!(
+            normalized_name("Visual Studio Code - Insiders"),
+            "visual studio code"
+        );
+        assert_eq!(normalized_name("Foo 2"), "foo");
+        assert_eq!(normalized_name("Bar (1)"), "bar");
+        assert_eq!(normalized_name("1Password 7"), "1password");
+        assert_eq!(normalized_name("Devonthink"), "devonthink");
+    }
+
+    #[test]
+    fn groups_by_bundle_id_and_name() {
+        let apps = vec![
+            app(
+                "/Applications/Xcode.app",
+                "Xcode",
+                Some("com.apple.dt.Xcode"),
+                Some("15.4"),
+            ),
+            app(
+                "/Applications/Slack.app",
+                "Slack",
+                Some("com.tinyspeck.slackmacgap"),
+                None,
+            ),
+            app(
+                "/Applications/Xcode-beta.app",
+                "Xcode-beta",
+                Some("com.apple.dt.Xcode"),
+                Some("16.0"),
+            ),
+            app(
+                "/Users/a/Applications/Foo.app",
+                "Foo",
+                Some("com.example.foo"),
+                Some("1.2"),
+            ),
+            app(
+                "/Applications/Foo.app",
+                "Foo",
+                Some("com.example.foo"),
+                Some("1.10"),
+            ),
+        ];
+        let groups = group_duplicates(&apps);
+        assert_eq!(groups, vec![vec![0, 2], vec![3, 4]]);
+        assert_eq!(newest(&apps, &groups[0]), 1);
+        assert_eq!(newest(&apps, &groups[1]), 1);
+    }
+}
@@ -0,0 +1,182 @@
+//! Typed errors surfaced to the frontend.
+//!
+//! Most of `core` uses `anyhow` internally; the operations the UI needs to
+//! tell apart (removal, bundle metadata, Finder) return [`CoreError`]
+//! directly, and every Tauri command converts whatever it gets into one.
+//! Commands serialize it as `{ code, message, path }` so the UI can branch
+//! on `code` instead of parsing messages.
+
+use serde::{Serialize, Serializer, ser::SerializeStruct};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use super::trash::Refusal;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CoreError {
+    #[error("{message}")]
+    PermissionDenied {
+        path: Option<PathBuf>,
+        message: String,
+    },
+    #[error("{message}")]
+    NotFound {
+        path: Option<PathBuf>,
+        message: String,
+    },
+    #[error("{name} is running")]
+    AppRunning { name: String, path: Option<PathBuf> },
+    /// Refused by the removal policy, app protection or an exclusion rule.
+    /// Nothing was touched.
+    #[error("{reason}")]
+    Protected {
+        path: Option<PathBuf>,
+        reason: String,
+    },
+    /// `RemovalMode::Trash` was requested and the Trash refused the item.
+    #[error("Could not move {} to Trash: {message}", path.display())]
+    TrashUnavailable { path: PathBuf, message: String },
+    /// The task was dropped before it could run, e.g. because the app is
+    /// quitting.
+    #[error("Cancelled")]
+    Cancelled,
+    /// Any other failure, I/O or otherwise.
+    #[error("{message}")]
+    Io {
+        path: Option<PathBuf>,
+        message: String,
+    },
+}
+
+impl CoreError {
+    /// Classify an I/O error on `path` by its kind.
+    pub fn io(path: &Path, e: io::Error) -> Self {
+        let message = format!("{}: {e}", path.display());
+        let path = Some(path.to_path_buf());
+        match e.kind() {
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied { path, message },
+            io::ErrorKind::NotFound => Self::NotFound { path, message },
+            _ => Self::Io { path, message },
+        }
+    }
+
+    pub fn refused(path: &Path, refusal: Refusal) -> Self {
+        Self::Protected {
+            path: Some(path.into()),
+            reason: refusal.to_string(),
+        }
+    }
+
+    /// A failure with no better classification.
+    pub fn other(message: impl Into<String>) -> Self {
+        Self::Io {
+            path: None,
+            message: message.into(),
+        }
+    }
+
+    /// Stable identifier for the frontend.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::PermissionDenied { .. } => "permissionDenied",
+            Self::NotFound { .. } => "notFound",
+            Self::AppRunning { .. } => "appRunning",
+            Self::Protected { .. } => "protected",
+            Self::TrashUnavailable { .. } => "trashUnavailable",
+            Self::Cancelled => "cancelled",
+            Self::Io { .. } => "io",
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::TrashUnavailable { path, .. } => Some(path),
+            Self::PermissionDenied { path, .. }
+            | Self::NotFound { path, .. }
+            | Self::AppRunning { path, .. }
+            | Self::Protected { path, .. }
+            | Self::Io { path, .. } => path.as_deref(),
+            Self::Cancelled => None,
+        }
+    }
+}
+
+/// Recover the typed error from an `anyhow` chain: a wrapped [`CoreError`]
+/// keeps its kind, an `io::Error` is classified by kind, and anything else
+/// becomes [`CoreError::Io`] with the full context chain. A [`Refusal`]
+/// doesn't know its path; convert it with [`CoreError::refused`] before it
+/// is wrapped.
+impl From<anyhow::Error> for CoreError {
+    fn from(e: anyhow::Error) -> Self {
+        let e = match e.downcast::<CoreError>() {
+            Ok(core) => return core,
+            Err(e) => e,
+        };
+        let message = format!("{e:#}");
+        let path = None;
+        match e
+            .chain()
+            .find_map(|c| c.downcast_ref::<io::Error>())
+            .map(io::Error::kind)
+        {
+            Some(io::ErrorKind::PermissionDenied) => Self::PermissionDenied { path, message },
+            Some(io::ErrorKind::NotFound) => Self::NotFound { path, message },
+            _ => Self::Io { path, message },
+        }
+    }
+}
+
+impl Serialize for CoreError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("CoreError", 3)?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("message", &self.to_string())?;
+        s.serialize_field("path", &self.path())?;
+        s.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_code_message_and_path() {
+        let e = CoreError::io(
+            Path::new("/tmp/x"),
+            io::Error::from(io::ErrorKind::PermissionDenied),
+        );
+        assert_eq!(
+            serde_json::to_value(&e).unwrap(),
+            serde_json::json!({
+                "code": "permissionDenied",
+                "message": "/tmp/x: permission denied",
+                "path": "/tmp/x",
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(CoreError::Cancelled).unwrap(),
+            serde_json::json!({ "code": "cancelled", "message": "Cancelled", "path": null })
+        );
+    }
+
+    #[test]
+    fn recovers_kind_from_anyhow() {
+        let wrapped = anyhow::Error::from(CoreError::Cancelled);
+        assert_eq!(CoreError::from(wrapped).code(), "cancelled");
+
+        let path = Path::new("/Library/Preferences");
+        let refused = anyhow::Error::from(CoreError::refused(path, Refusal::LibraryRoot));
+        let refused = CoreError::from(refused);
+        assert_eq!(refused.code(), "protected");
+        assert_eq!(refused.path(), Some(path));
+
+        let io = anyhow::Error::from(io::Error::from(io::ErrorKind::NotFound)).context("Read x");
+        assert_eq!(CoreError::from(io).code(), "notFound");
+
+        let other = anyhow::anyhow!("inner").context("outer");
+        assert_eq!(CoreError::from(other).to_string(), "outer: inner");
+    }
+}
@@ -0,0 +1,200 @@
+//! User exclusion rules: paths the uninstaller must never suggest or touch.
+//!
+//! Rules come in three shapes (see `models::ExclusionRules`):
+//!
+//! - exact paths (`~/Library/Application Support/Foo/license.dat`),
+//! - globs (`~/Library/Application Support/Code/User/**`),
+//! - bundle-id patterns (`com.microsoft.*`), matched against the file name
+//!   of a candidate, with and without its extension.
+//!
+//! A candidate is excluded when it matches a rule, lies inside an excluded
+//! path, or contains one (removing a parent would take the kept item with
+//! it). `find_related_paths` filters with these rules and `run_uninstall`
+//! re-checks every path before removing it.
+
+use anyhow::{Context, Result};
+use glob::{MatchOptions, Pattern};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::paths::{expand_home, user_home};
+use crate::models::ExclusionRules;
+
+const OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: false,
+    require_literal_leading_dot: false,
+};
+
+/// [`ExclusionRules`] with `~` expanded and patterns compiled.
+#[derive(Clone, Debug, Default)]
+pub struct Exclusions {
+    paths: Vec<(PathBuf, String)>,
+    globs: Vec<(Pattern, PathBuf, String)>,
+    bundle_ids: Vec<(Pattern, String)>,
+}
+
+/// The part of a glob before its first wildcard component.
+fn literal_prefix(pattern: &Path) -> PathBuf {
+    pattern
+        .components()
+        .take_while(|c| {
+            !c.as_os_str()
+                .to_string_lossy()
+                .contains(['*', '?', '[', ']'])
+        })
+        .collect()
+}
+
+impl Exclusions {
+    /// Compile `rules`, expanding `~` to `home`. Fails on an invalid glob.
+    pub fn compile(rules: &ExclusionRules, home: &Path) -> Result<Self> {
+        let mut out = Self::default();
+        for p in &rules.paths {
+            out.paths.push((expand_home(p, home), p.clone()));
+        }
+        for g in &rules.globs {
+            let expanded = expand_home(g, home);
+            let pattern = Pattern::new(&expanded.to_string_lossy())
+                .with_context(|| format!("Invalid glob {g:?}"))?;
+            out.globs
+                .push((pattern, literal_prefix(&expanded), g.clone()));
+        }
+        for b in &rules.bundle_ids {
+            let pattern =
+                Pattern::new(b).with_context(|| format!("Invalid bundle id pattern {b:?}"))?;
+            out.bundle_ids.push((pattern, b.clone()));
+        }
+        Ok(out)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty() && self.globs.is_empty() && self.bundle_ids.is_empty()
+    }
+
+    /// The rule that excludes `candidate`, if any.
+    pub fn matching_rule(&self, candidate: &Path) -> Option<&str> {
+        for (path, rule) in &self.paths {
+            if candidate.starts_with(path) || path.starts_with(candidate) {
+                return Some(rule);
+            }
+        }
+        for (pattern, prefix, rule) in &self.globs {
+            let hit = candidate
+                .ancestors()
+                .any(|a| pattern.matches_path_with(a, OPTIONS))
+                || (prefix.starts_with(candidate) && prefix != Path::new("/"));
+            if hit {
+                return Some(rule);
+            }
+        }
+        let name = candidate.file_name()?.to_string_lossy();
+        let stem = candidate.file_stem().map(|s| s.to_string_lossy());
+        for (pattern, rule) in &self.bundle_ids {
+            if pattern.matches_with(&name, OPTIONS)
+                || stem
+                    .as_deref()
+                    .is_some_and(|s| pattern.matches_with(s, OPTIONS))
+            {
+                return Some(rule);
+            }
+        }
+        None
+    }
+
+    pub fn is_excluded(&self, candidate: &Path) -> bool {
+        self.matching_rule(candidate).is_some()
+    }
+}
+
+/// File name of the rules file inside the app config directory.
+pub const EXCLUSIONS_FILE: &str = "exclusions.json";
+
+/// Load and compile the rules file for the current user.
+pub fn load_user_exclusions(path: &Path) -> Result<Exclusions> {
+    Exclusions::compile(&load_exclusions(path)?, &user_home())
+}
+
+/// Read the rules file; a missing file means no rules.
+pub fn load_exclusions(path: &Path) -> Result<ExclusionRules> {
+    match fs::read(path) {
+        Ok(raw) => serde_json::from_slice(&raw)
+            .with_context(|| format!("Invalid exclusions file {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ExclusionRules::default()),
+        Err(e) => Err(e).with_context(|| format!("Read {}", path.display())),
+    }
+}
+
+/// Validate and write the rules file, creating its directory if needed.
+pub fn save_exclusions(path: &Path, rules: &ExclusionRules) -> Result<()> {
+    Exclusions::compile(rules, &user_home())?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Create {}", dir.display()))?;
+    }
+    let json = serde_json::to_vec_pretty(rules)?;
+    fs::write(path, json).with_context(|| format!("Write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> ExclusionRules {
+        ExclusionRules {
+            paths: vec!["~/Library/Application Support/Foo/license.dat".into()],
+            globs: vec!["~/Library/Application Support/Code/User/**".into()],
+            bundle_ids: vec!["com.apple.*".into()],
+        }
+    }
+
+    #[test]
+    fn matches_paths_globs_and_bundle_ids() {
+        let home = Path::new("/Users/a");
+        let ex = Exclusions::compile(&rules(), home).unwrap();
+        let lib = home.join("Library");
+        let support = lib.join("Application Support");
+
+        // Exact path, its parents, and its children.
+        assert!(ex.is_excluded(&support.join("Foo/license.dat")));
+        assert!(ex.is_excluded(&support.join("Foo")));
+        assert!(!ex.is_excluded(&support.join("Foobar")));
+
+        // Glob, the folder it lives in, and everything under it.
+        assert!(ex.is_excluded(&support.join("Code/User/settings.json")));
+        assert!(ex.is_excluded(&support.join("Code")));
+        assert!(!ex.is_excluded(&support.join("Code - Insiders")));
+
+        // Bundle-id pattern on the file name, with or without extension.
+        assert_eq!(
+            ex.matching_rule(&lib.join("Preferences/com.apple.Notes.plist")),
+            Some("com.apple.*")
+        );
+        assert!(!ex.is_excluded(&lib.join("Caches/com.example.notes")));
+
+        assert!(
+            Exclusions::compile(
+                &ExclusionRules {
+                    globs: vec!["[".into()],
+                    ..Default::default()
+                },
+                home
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn round_trips_rules_file() {
+        let dir = std::env::temp_dir().join(format!("exclusions-test-{}", std::process::id()));
+        let path = dir.join("nested").join("exclusions.json");
+        let missing = load_exclusions(&path).unwrap();
+        save_exclusions(&path, &rules()).unwrap();
+        let loaded = load_exclusions(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(missing, ExclusionRules::default());
+        assert_eq!(loaded, rules());
+    }
+}
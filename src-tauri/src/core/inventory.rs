@@ -0,0 +1,283 @@
+//! Serialize the scanned app list for inventory tooling.
+//!
+//! One [`InventoryEntry`] per app, rendered as JSON, CSV or a Markdown
+//! table. With related paths, CSV lists them as `path=bytes` pairs joined
+//! with `;` and Markdown as one `path (bytes)` line each; an unreadable
+//! size is left empty (`?` in Markdown). Sizing walks every bundle (and,
+//! with related paths, every related folder), so this is an explicit
+//! export, never part of `list_apps`.
+
+use anyhow::{Context, Result};
+use std::{fmt::Write as _, fs, path::Path};
+
+use super::{
+    apps::{compute_size, run_scan},
+    casks::{merge_zap_paths, scan_casks},
+    exclusions::Exclusions,
+    progress::ProgressSink,
+    related::find_related_paths,
+};
+use crate::models::{AppInfo, ExportFormat, InventoryEntry, InventoryItem, Settings};
+
+fn install_source_label(app: &InventoryEntry) -> String {
+    serde_json::to_value(app.install_source)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Size every app and, if asked, collect its related paths with sizes.
+/// `related_max_depth` is passed to `find_related_paths`.
+pub fn build_inventory(
+    apps: &[AppInfo],
+    include_related: bool,
+    related_max_depth: usize,
+    exclusions: &Exclusions,
+) -> Vec<InventoryEntry> {
+    let casks = if include_related {
+        scan_casks()
+    } else {
+        Vec::new()
+    };
+    apps.iter()
+        .map(|app| {
+            let related = include_related.then(|| {
+                let mut paths = find_related_paths(
+                    app.bundle_id.as_deref(),
+                    Some(&app.name),
+                    related_max_depth,
+                    exclusions,
+                );
+                merge_zap_paths(&casks, app.cask.as_deref(), &mut paths);
+                paths.retain(|p| !exclusions.is_excluded(p));
+                paths
+                    .into_iter()
+                    .map(|path| InventoryItem {
+                        size: compute_size(&path),
+                        path,
+                    })
+                    .collect()
+            });
+            InventoryEntry {
+                name: app.name.clone(),
+                bundle_id: app.bundle_id.clone(),
+                version: app.version.clone(),
+                path: app.path.clone(),
+                size: compute_size(&app.path),
+                running: app.running,
+                install_source: app.install_source,
+                related,
+            }
+        })
+        .collect()
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or newline.
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn md_cell(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', " ")
+}
+
+fn opt_size(size: Option<u64>) -> String {
+    size.map(|s| s.to_string()).unwrap_or_default()
+}
+
+fn related_total(entry: &InventoryEntry) -> Option<u64> {
+    entry
+        .related
+        .as_ref()
+        .map(|r| r.iter().filter_map(|i| i.size).sum())
+}
+
+pub fn render_inventory(entries: &[InventoryEntry], format: ExportFormat) -> Result<String> {
+    let with_related = entries.iter().any(|e| e.related.is_some());
+    let mut out = String::new();
+    match format {
+        ExportFormat::Json => {
+            out = serde_json::to_string_pretty(entries).context("Serialize inventory")?;
+            out.push('\n');
+        }
+        ExportFormat::Csv => {
+            out.push_str("name,bundle_id,version,path,size,running,install_source");
+            if with_related {
+                out.push_str(",related_paths,related_bytes");
+            }
+            out.push('\n');
+            for e in entries {
+                let mut row = vec![
+                    csv_field(&e.name),
+                    csv_field(e.bundle_id.as_deref().unwrap_or_default()),
+                    csv_field(e.version.as_deref().unwrap_or_default()),
+                    csv_field(&e.path.to_string_lossy()),
+                    opt_size(e.size),
+                    e.running.to_string(),
+                    install_source_label(e),
+                ];
+                if with_related {
+                    let items: Vec<String> = e
+                        .related
+                        .iter()
+                        .flatten()
+                        .map(|i| format!("{}={}", i.path.to_string_lossy(), opt_size(i.size)))
+                        .collect();
+                    row.push(csv_field(&items.join(";")));
+                    row.push(opt_size(related_total(e)));
+                }
+                out.push_str(&row.join(","));
+                out.push('\n');
+            }
+        }
+        ExportFormat::Markdown => {
+            out.push_str("| Name | Bundle ID | Version | Path | Size | Running | Source |");
+            if with_related {
+                out.push_str(" Related | Related size |");
+            }
+            out.push('\n');
+            out.push_str("| --- | --- | --- | --- | ---: | --- | --- |");
+            if with_related {
+                out.push_str(" ---: | ---: |");
+            }
+            out.push('\n');
+            for e in entries {
+                let _ = write!(
+                    out,
+                    "| {} | {} | {} | `{}` | {} | {} | {} |",
+                    md_cell(&e.name),
+                    md_cell(e.bundle_id.as_deref().unwrap_or_default()),
+                    md_cell(e.version.as_deref().unwrap_or_default()),
+                    md_cell(&e.path.to_string_lossy()),
+                    opt_size(e.size),
+                    if e.running { "yes" } else { "no" },
+                    install_source_label(e),
+                );
+                if with_related {
+                    let items: Vec<String> = e
+                        .related
+                        .iter()
+                        .flatten()
+                        .map(|i| {
+                            format!(
+                                "`{}` ({})",
+                                md_cell(&i.path.to_string_lossy()),
+                                i.size.map_or("?".to_string(), |s| s.to_string())
+                            )
+                        })
+                        .collect();
+                    let _ = write!(
+                        out,
+                        " {} | {} |",
+                        items.join("<br>"),
+                        opt_size(related_total(e)),
+                    );
+                }
+                out.push('\n');
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Scan the configured roots, build and write the inventory to `path`.
+/// Returns the number of apps written. The scan is reported to `sink`.
+pub fn export_inventory(
+    format: ExportFormat,
+    path: &Path,
+    include_related: bool,
+    settings: &Settings,
+    exclusions: &Exclusions,
+    sink: &dyn ProgressSink,
+) -> Result<usize> {
+    let apps = run_scan(&settings.user_scan_roots(), sink)?;
+    let entries = build_inventory(
+        &apps,
+        include_related,
+        settings.related_max_depth,
+        exclusions,
+    );
+    let rendered = render_inventory(&entries, format)?;
+    fs::write(path, rendered).with_context(|| format!("Write {}", path.display()))?;
+    Ok(entries.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::InstallSource;
+    use std::path::PathBuf;
+
+    fn entries() -> Vec<InventoryEntry> {
+        vec![
+            InventoryEntry {
+                name: "Foo, Pro".into(),
+                bundle_id: Some("com.example.foo".into()),
+                version: Some("1.2".into()),
+                path: PathBuf::from("/Applications/Foo, Pro.app"),
+                size: Some(2048),
+                running: true,
+                install_source: InstallSource::Homebrew,
+                related: Some(vec![
+                    InventoryItem {
+                        path: PathBuf::from("/Users/a/Library/Caches/com.example.foo"),
+                        size: Some(100),
+                    },
+                    InventoryItem {
+                        path: PathBuf::from("/Users/a/Library/Preferences/com.example.foo.plist"),
+                        size: None,
+                    },
+                ]),
+            },
+            InventoryEntry {
+                name: "Bar|Baz".into(),
+                bundle_id: None,
+                version: None,
+                path: PathBuf::from("/Applications/Bar.app"),
+                size: None,
+                running: false,
+                install_source: InstallSource::AppStore,
+                related: Some(Vec::new()),
+            },
+        ]
+    }
+
+    #[test]
+    fn renders_csv_with_quoting() {
+        let csv = render_inventory(&entries(), ExportFormat::Csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "name,bundle_id,version,path,size,running,install_source,related_paths,related_bytes"
+        );
+        assert_eq!(
+            lines[1],
+            "\"Foo, Pro\",com.example.foo,1.2,\"/Applications/Foo, Pro.app\",2048,true,homebrew,\
+             /Users/a/Library/Caches/com.example.foo=100;/Users/a/Library/Preferences/com.example.foo.plist=,100"
+        );
+        assert_eq!(
+            lines[2],
+            "Bar|Baz,,,/Applications/Bar.app,,false,appStore,,0"
+        );
+    }
+
+    #[test]
+    fn renders_markdown_and_json() {
+        let md = render_inventory(&entries(), ExportFormat::Markdown).unwrap();
+        assert!(md.lines().nth(3).unwrap().starts_with("| Bar\\|Baz |"));
+        assert!(md.lines().nth(2).unwrap().ends_with(
+            "| yes | homebrew | `/Users/a/Library/Caches/com.example.foo` (100)<br>\
+                     `/Users/a/Library/Preferences/com.example.foo.plist` (?) | 100 |"
+        ));
+
+        let json = render_inventory(&entries(), ExportFormat::Json).unwrap();
+        let v: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(v[0]["bundleId"], "com.example.foo");
+        assert_eq!(v[0]["related"][0]["size"], 100);
+        assert_eq!(v[1]["installSource"], "appStore");
+    }
+}
@@ -0,0 +1,304 @@
+//! Minimal Mach-O / fat (universal) header parsing.
+//!
+//! Only the parts the uninstaller needs: the list of architecture slices in
+//! a file (to flag Intel-only apps) and, per slice, the location of the
+//! embedded code signature. This is plain byte parsing with no macOS APIs,
+//! so it runs (and is tested) on any platform.
+
+use anyhow::{Result, bail};
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use crate::models::Architecture;
+
+const MH_MAGIC: u32 = 0xfeed_face;
+const MH_CIGAM: u32 = 0xcefa_edfe;
+const MH_MAGIC_64: u32 = 0xfeed_facf;
+const MH_CIGAM_64: u32 = 0xcffa_edfe;
+const FAT_MAGIC: u32 = 0xcafe_babe;
+const FAT_MAGIC_64: u32 = 0xcafe_babf;
+
+const LC_CODE_SIGNATURE: u32 = 0x1d;
+
+const CPU_ARCH_ABI64: u32 = 0x0100_0000;
+const CPU_TYPE_X86: u32 = 7;
+const CPU_TYPE_X86_64: u32 = CPU_TYPE_X86 | CPU_ARCH_ABI64;
+const CPU_TYPE_ARM64: u32 = 12 | CPU_ARCH_ABI64;
+const CPU_TYPE_POWERPC: u32 = 18;
+const CPU_TYPE_POWERPC64: u32 = CPU_TYPE_POWERPC | CPU_ARCH_ABI64;
+/// The high byte of `cpu_subtype` holds capability bits (e.g. the pointer
+/// authentication ABI version on arm64e), not the subtype itself.
+const CPU_SUBTYPE_MASK: u32 = 0x00ff_ffff;
+const CPU_SUBTYPE_ARM64E: u32 = 2;
+
+/// Java class files share `0xcafebabe`; a real fat header never lists this
+/// many architectures.
+const MAX_FAT_ARCHS: u32 = 32;
+
+/// One architecture slice. A thin Mach-O is a single slice spanning the
+/// whole file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Slice {
+    pub cpu_type: u32,
+    pub cpu_subtype: u32,
+    pub offset: u64,
+    pub size: u64,
+}
+
+impl Slice {
+    pub fn architecture(&self) -> Architecture {
+        match self.cpu_type {
+            CPU_TYPE_ARM64 if self.cpu_subtype & CPU_SUBTYPE_MASK == CPU_SUBTYPE_ARM64E => {
+                Architecture::Arm64e
+            }
+            CPU_TYPE_ARM64 => Architecture::Arm64,
+            CPU_TYPE_X86_64 => Architecture::X86_64,
+            CPU_TYPE_X86 => Architecture::I386,
+            CPU_TYPE_POWERPC | CPU_TYPE_POWERPC64 => Architecture::PowerPc,
+            _ => Architecture::Other,
+        }
+    }
+}
+
+fn be32(b: &[u8], at: usize) -> Option<u32> {
+    b.get(at..at + 4)
+        .map(|s| u32::from_be_bytes([s[0], s[1], s[2], s[3]]))
+}
+
+fn be64(b: &[u8], at: usize) -> Option<u64> {
+    b.get(at..at + 8).map(|s| {
+        let mut a = [0u8; 8];
+        a.copy_from_slice(s);
+        u64::from_be_bytes(a)
+    })
+}
+
+fn read32(b: &[u8], at: usize, little: bool) -> Option<u32> {
+    let v = be32(b, at)?;
+    Some(if little { v.swap_bytes() } else { v })
+}
+
+/// True if `head` starts with a Mach-O or fat magic number.
+pub fn is_macho(head: &[u8]) -> bool {
+    matches!(
+        be32(head, 0),
+        Some(MH_MAGIC | MH_CIGAM | MH_MAGIC_64 | MH_CIGAM_64)
+    ) || fat_arch_count(head).is_some()
+}
+
+fn fat_arch_count(head: &[u8]) -> Option<u32> {
+    match be32(head, 0)? {
+        FAT_MAGIC | FAT_MAGIC_64 => {
+            let n = be32(head, 4)?;
+            (n > 0 && n <= MAX_FAT_ARCHS).then_some(n)
+        }
+        _ => None,
+    }
+}
+
+fn fat_entry(head: &[u8], at: usize, is64: bool) -> Option<Slice> {
+    let (offset, size) = if is64 {
+        (be64(head, at + 8)?, be64(head, at + 16)?)
+    } else {
+        (be32(head, at + 8)? as u64, be32(head, at + 12)? as u64)
+    };
+    Some(Slice {
+        cpu_type: be32(head, at)?,
+        cpu_subtype: be32(head, at + 4)?,
+        offset,
+        size,
+    })
+}
+
+/// Parse the slice table from the first bytes of a file. `file_len` is the
+/// total file size, used as the extent of a thin Mach-O.
+pub fn parse_slices(head: &[u8], file_len: u64) -> Result<Vec<Slice>> {
+    if let Some(n) = fat_arch_count(head) {
+        let is64 = be32(head, 0) == Some(FAT_MAGIC_64);
+        let entry = if is64 { 32 } else { 20 };
+        let mut out = Vec::with_capacity(n as usize);
+        for i in 0..n as usize {
+            match fat_entry(head, 8 + i * entry, is64) {
+                Some(s) => out.push(s),
+                None => bail!("Truncated fat header"),
+            }
+        }
+        return Ok(out);
+    }
+
+    let little = match be32(head, 0) {
+        Some(MH_CIGAM | MH_CIGAM_64) => true,
+        Some(MH_MAGIC | MH_MAGIC_64) => false,
+        _ => bail!("Not a Mach-O file"),
+    };
+    match (read32(head, 4, little), read32(head, 8, little)) {
+        (Some(cpu_type), Some(cpu_subtype)) => Ok(vec![Slice {
+            cpu_type,
+            cpu_subtype,
+            offset: 0,
+            size: file_len,
+        }]),
+        _ => bail!("Truncated Mach-O header"),
+    }
+}
+
+/// Read the slice table of the Mach-O file at `path`.
+pub fn read_slices(path: &Path) -> Result<Vec<Slice>> {
+    let mut f = File::open(path)?;
+    let len = f.metadata()?.len();
+    let mut head = vec![0u8; 4096];
+    let n = read_up_to(&mut f, &mut head)?;
+    head.truncate(n);
+    parse_slices(&head, len)
+}
+
+/// Architectures of the Mach-O file at `path`, in slice order. Empty if the
+/// file is missing or not a Mach-O (e.g. a shell-script launcher).
+pub fn read_architectures(path: &Path) -> Vec<Architecture> {
+    read_slices(path)
+        .map(|slices| slices.iter().map(Slice::architecture).collect())
+        .unwrap_or_default()
+}
+
+/// Given the header and load commands of a single thin Mach-O image,
+/// return the `(offset, size)` of its code signature relative to the start
+/// of the image.
+pub fn code_signature_range(image: &[u8]) -> Option<(u64, u64)> {
+    let (little, header_len) = match be32(image, 0)? {
+        MH_CIGAM => (true, 28),
+        MH_MAGIC => (false, 28),
+        MH_CIGAM_64 => (true, 32),
+        MH_MAGIC_64 => (false, 32),
+        _ => return None,
+    };
+    let ncmds = read32(image, 16, little)?;
+    let mut at = header_len;
+    for _ in 0..ncmds {
+        let cmd = read32(image, at, little)?;
+        let cmdsize = read32(image, at + 4, little)? as usize;
+        if cmdsize < 8 {
+            return None;
+        }
+        if cmd == LC_CODE_SIGNATURE {
+            let off = read32(image, at + 8, little)? as u64;
+            let size = read32(image, at + 12, little)? as u64;
+            return Some((off, size));
+        }
+        at += cmdsize;
+    }
+    None
+}
+
+/// Read the embedded code signature (the raw superblob) of the first slice
+/// of the Mach-O file at `path`. `Ok(None)` means the file is a valid
+/// Mach-O without a signature.
+pub fn read_code_signature(path: &Path) -> Result<Option<Vec<u8>>> {
+    let mut f = File::open(path)?;
+    let slices = read_slices(path)?;
+    let Some(slice) = slices.first() else {
+        return Ok(None);
+    };
+
+    let mut image = vec![0u8; 64 * 1024];
+    f.seek(SeekFrom::Start(slice.offset))?;
+    let n = read_up_to(&mut f, &mut image)?;
+    image.truncate(n);
+
+    let Some((off, size)) = code_signature_range(&image) else {
+        return Ok(None);
+    };
+    if off.saturating_add(size) > slice.size {
+        bail!("Code signature lies outside its slice");
+    }
+    let mut blob = vec![0u8; size as usize];
+    f.seek(SeekFrom::Start(slice.offset + off))?;
+    f.read_exact(&mut blob)?;
+    Ok(Some(blob))
+}
+
+fn read_up_to(f: &mut File, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match f.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Little-endian 64-bit Mach-O header with a single
+    /// `LC_CODE_SIGNATURE` load command.
+    fn thin_arm64_with_signature(dataoff: u32, datasize: u32) -> Vec<u8> {
+        let mut b = Vec::new();
+        for v in [MH_MAGIC_64, 0x0100_000c, 0, 2, 1, 16, 0, 0] {
+            b.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in [LC_CODE_SIGNATURE, 16, dataoff, datasize] {
+            b.extend_from_slice(&v.to_le_bytes());
+        }
+        b
+    }
+
+    #[test]
+    fn parses_thin_header_and_code_signature() {
+        let image = thin_arm64_with_signature(0x4000, 0x200);
+        let slices = parse_slices(&image, 0x4200).unwrap();
+        assert_eq!(
+            slices,
+            vec![Slice {
+                cpu_type: 0x0100_000c,
+                cpu_subtype: 0,
+                offset: 0,
+                size: 0x4200,
+            }]
+        );
+        assert_eq!(code_signature_range(&image), Some((0x4000, 0x200)));
+    }
+
+    #[test]
+    fn parses_fat_header() {
+        let mut b = Vec::new();
+        for v in [FAT_MAGIC, 2] {
+            b.extend_from_slice(&v.to_be_bytes());
+        }
+        for v in [0x0100_0007u32, 3, 0x1000, 0x2000, 12] {
+            b.extend_from_slice(&v.to_be_bytes());
+        }
+        for v in [0x0100_000cu32, 0, 0x4000, 0x3000, 14] {
+            b.extend_from_slice(&v.to_be_bytes());
+        }
+        let slices = parse_slices(&b, 0x7000).unwrap();
+        assert_eq!(slices.len(), 2);
+        assert_eq!(slices[1].offset, 0x4000);
+        assert_eq!(slices[1].size, 0x3000);
+        let archs: Vec<_> = slices.iter().map(Slice::architecture).collect();
+        assert_eq!(archs, vec![Architecture::X86_64, Architecture::Arm64]);
+    }
+
+    #[test]
+    fn arm64e_ignores_capability_bits() {
+        let slice = Slice {
+            cpu_type: CPU_TYPE_ARM64,
+            cpu_subtype: 0x8000_0002,
+            offset: 0,
+            size: 0,
+        };
+        assert_eq!(slice.architecture(), Architecture::Arm64e);
+    }
+
+    #[test]
+    fn rejects_java_class_files() {
+        // 0xcafebabe followed by a class-file version, not an arch count.
+        let class = [0xca, 0xfe, 0xba, 0xbe, 0x00, 0x00, 0x00, 0x41];
+        assert!(!is_macho(&class));
+        assert!(parse_slices(&class, 8).is_err());
+    }
+}
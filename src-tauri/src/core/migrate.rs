@@ -0,0 +1,354 @@
+//! Carry an app's data over to a new bundle id.
+//!
+//! Switching from a beta to a stable build, or from a direct download to
+//! the App Store version, changes the bundle id and with it where the app
+//! looks for its data. [`plan_migration_in`] maps the old id's
+//! Application Support folder and preferences (including `ByHost`) to the
+//! new id's names. A sandbox container can't be renamed, since macOS ties
+//! it to the app that created it; its documents, Application Support data
+//! and preferences move into the new app's container instead, once that
+//! exists. Nothing is ever merged or overwritten: a move whose destination
+//! exists is blocked, and so is an excluded source.
+//!
+//! Every move is a rename within one folder, so it is atomic; if one
+//! fails, the moves already made are renamed back.
+
+use home::home_dir;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    error::CoreError, exclusions::Exclusions, running::is_app_running_simple,
+    transaction::preflight,
+};
+use crate::models::{FailureKind, MigrationMove, MigrationReport, UninstallFailure};
+
+/// Refuse ids that could name a path outside the Library folder.
+fn check_bundle_id(id: &str) -> Result<(), CoreError> {
+    let valid = !id.is_empty()
+        && !id.starts_with('.')
+        && !id.contains("..")
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+    if valid {
+        Ok(())
+    } else {
+        Err(CoreError::other(format!("{id:?} is not a bundle id")))
+    }
+}
+
+/// Old and new locations for every id-named item under `home`'s Library,
+/// whether or not they exist, with a reason when the move can't be made.
+fn candidates(home: &Path, from: &str, to: &str) -> Vec<(PathBuf, PathBuf, Option<String>)> {
+    let lib = home.join("Library");
+    let prefs = lib.join("Preferences");
+    let support = lib.join("Application Support");
+    let mut v = vec![
+        (support.join(from), support.join(to), None),
+        (
+            prefs.join(format!("{from}.plist")),
+            prefs.join(format!("{to}.plist")),
+            None,
+        ),
+    ];
+    // Per-machine preferences: `ByHost/<bundle id>.<hardware UUID>.plist`.
+    let by_host = prefs.join("ByHost");
+    if let Ok(entries) = fs::read_dir(&by_host) {
+        let prefix = format!("{from}.");
+        let mut hosts: Vec<(PathBuf, PathBuf, Option<String>)> = entries
+            .flatten()
+            .filter_map(|e| {
+                let name = e.file_name().to_string_lossy().into_owned();
+                let host = name.strip_prefix(&prefix)?;
+                // `com.foo.` also prefixes `com.foo.helper.<uuid>.plist`.
+                (host.matches('.').count() == 1 && host.ends_with(".plist"))
+                    .then(|| (e.path(), by_host.join(format!("{to}.{host}")), None))
+            })
+            .collect();
+        hosts.sort();
+        v.extend(hosts);
+    }
+
+    // A container is tied to its bundle id and signature by the metadata
+    // plist at its root, so it can't be renamed; only its data can move
+    // into the new app's own container.
+    let containers = lib.join("Containers");
+    let (old, new) = (containers.join(from), containers.join(to));
+    if fs::symlink_metadata(&old).is_ok() {
+        if fs::symlink_metadata(&new).is_ok() {
+            v.extend(container_moves(
+                &old.join("Data"),
+                &new.join("Data"),
+                from,
+                to,
+            ));
+        } else {
+            v.push((
+                old,
+                new,
+                Some(
+                    "Containers belong to one app. Open the new app once so macOS creates its container, then migrate again."
+                        .into(),
+                ),
+            ));
+        }
+    }
+    v
+}
+
+/// Moves from one container's `Data` folder into another's: the entries of
+/// `Documents` and `Library/Application Support`, and the app's own
+/// preferences plist under the new id's name.
+fn container_moves(
+    old: &Path,
+    new: &Path,
+    from: &str,
+    to: &str,
+) -> Vec<(PathBuf, PathBuf, Option<String>)> {
+    let mut v = Vec::new();
+    for dir in ["Documents", "Library/Application Support"] {
+        let Ok(entries) = fs::read_dir(old.join(dir)) else {
+            continue;
+        };
+        let mut names: Vec<String> = entries
+            .flatten()
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .filter(|n| !n.starts_with('.'))
+            .collect();
+        names.sort();
+        for name in names {
+            let target = if name == from { to } else { &name };
+            v.push((old.join(dir).join(&name), new.join(dir).join(target), None));
+        }
+    }
+    let prefs = Path::new("Library").join("Preferences");
+    v.push((
+        old.join(&prefs).join(format!("{from}.plist")),
+        new.join(&prefs).join(format!("{to}.plist")),
+        None,
+    ));
+    v
+}
+
+/// The moves migrating from `from` to `to` takes for `home`, with blocked
+/// ones explained. Sources that don't exist are left out.
+pub fn plan_migration_in(
+    home: &Path,
+    from: &str,
+    to: &str,
+    exclusions: &Exclusions,
+) -> Result<Vec<MigrationMove>, CoreError> {
+    check_bundle_id(from)?;
+    check_bundle_id(to)?;
+    if from == to {
+        return Err(CoreError::other("Both bundle ids are the same"));
+    }
+    Ok(candidates(home, from, to)
+        .into_iter()
+        .filter(|(src, _, _)| fs::symlink_metadata(src).is_ok())
+        .map(|(from, to, blocked)| {
+            let blocked = if blocked.is_some() {
+                blocked
+            } else if let Some(rule) = exclusions.matching_rule(&from) {
+                Some(format!("Excluded by rule {rule:?}"))
+            } else if fs::symlink_metadata(&to).is_ok() {
+                Some(format!("{} already exists", to.display()))
+            } else {
+                preflight(&from).err().map(|e| e.to_string())
+            };
+            MigrationMove { from, to, blocked }
+        })
+        .collect())
+}
+
+/// [`plan_migration_in`] for the current user.
+pub fn plan_migration(
+    from: &str,
+    to: &str,
+    exclusions: &Exclusions,
+) -> Result<Vec<MigrationMove>, CoreError> {
+    let home = home_dir().ok_or_else(|| CoreError::other("No home directory"))?;
+    plan_migration_in(&home, from, to, exclusions)
+}
+
+/// Plan and perform the migration for `home`. Blocked moves are reported
+/// as refused; a failed rename undoes the earlier ones and returns the
+/// error.
+pub fn migrate_in(
+    home: &Path,
+    from: &str,
+    to: &str,
+    exclusions: &Exclusions,
+) -> Result<MigrationReport, CoreError> {
+    let mut report = MigrationReport {
+        from_bundle_id: from.to_string(),
+        to_bundle_id: to.to_string(),
+        moved: Vec::new(),
+        failed: Vec::new(),
+    };
+    for m in plan_migration_in(home, from, to, exclusions)? {
+        if let Some(reason) = m.blocked.clone() {
+            report.failed.push(UninstallFailure {
+                path: m.from,
+                error: reason.clone(),
+                kind: FailureKind::Refused { reason },
+            });
+            continue;
+        }
+        // `rename` replaces an existing file, so check again right before.
+        let result = if fs::symlink_metadata(&m.to).is_ok() {
+            Err(CoreError::Protected {
+                path: Some(m.to.clone()),
+                reason: format!("{} already exists", m.to.display()),
+            })
+        } else {
+            m.to.parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|()| fs::rename(&m.from, &m.to))
+                .map_err(|e| CoreError::io(&m.from, e))
+        };
+        if let Err(e) = result {
+            for done in report.moved.iter().rev() {
+                if let Err(undo) = fs::rename(&done.to, &done.from) {
+                    log::error!("Could not move {} back: {undo}", done.to.display());
+                }
+            }
+            return Err(e);
+        }
+        report.moved.push(m);
+    }
+    Ok(report)
+}
+
+/// Move the data of bundle id `from` to `to` for the current user. Refuses
+/// while either app runs, since it would keep writing to the old location
+/// or create the new one.
+pub fn migrate_app_data(
+    from: &str,
+    to: &str,
+    exclusions: &Exclusions,
+) -> Result<MigrationReport, CoreError> {
+    for id in [from, to] {
+        if is_app_running_simple(None, Some(id), None) {
+            return Err(CoreError::AppRunning {
+                name: id.to_string(),
+                path: None,
+            });
+        }
+    }
+    let home = home_dir().ok_or_else(|| CoreError::other("No home directory"))?;
+    migrate_in(&home, from, to, exclusions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_bundle_id_locations_without_overwriting() {
+        let home = std::env::temp_dir().join(format!("migrate-test-{}", std::process::id()));
+        let lib = home.join("Library");
+        let write = |rel: &str| {
+            let p = lib.join(rel);
+            fs::create_dir_all(p.parent().unwrap()).unwrap();
+            fs::write(p, rel).unwrap();
+        };
+        let old = "Containers/com.foo.beta";
+        let new = "Containers/com.foo";
+        for rel in [
+            "Application Support/com.foo.beta/db.sqlite",
+            "Preferences/com.foo.beta.plist",
+            "Preferences/ByHost/com.foo.beta.0000-1111.plist",
+            "Preferences/ByHost/com.foo.beta.helper.0000-1111.plist",
+            &format!("{old}/.com.apple.containermanagerd.metadata.plist"),
+            &format!("{old}/Data/Documents/notes.txt"),
+            &format!("{old}/Data/Library/Application Support/com.foo.beta/state.db"),
+            &format!("{old}/Data/Library/Preferences/com.foo.beta.plist"),
+            // The new build already created its container and a document.
+            &format!("{new}/.com.apple.containermanagerd.metadata.plist"),
+            &format!("{new}/Data/Documents/notes.txt"),
+        ] {
+            write(rel);
+        }
+        let none = Exclusions::default();
+
+        let plan = plan_migration_in(&home, "com.foo.beta", "com.foo", &none).unwrap();
+        let no_container =
+            plan_migration_in(&home, "com.foo.beta", "com.foo.other", &none).unwrap();
+        let report = migrate_in(&home, "com.foo.beta", "com.foo", &none).unwrap();
+        let prefs = fs::read_to_string(lib.join("Preferences/com.foo.plist")).unwrap();
+        let inner_prefs =
+            fs::read_to_string(lib.join(new).join("Data/Library/Preferences/com.foo.plist"))
+                .unwrap();
+        let state = lib
+            .join(new)
+            .join("Data/Library/Application Support/com.foo/state.db")
+            .exists();
+        let notes = fs::read_to_string(lib.join(new).join("Data/Documents/notes.txt")).unwrap();
+        let metadata_kept = lib
+            .join(old)
+            .join(".com.apple.containermanagerd.metadata.plist")
+            .exists();
+        let bad_id = plan_migration_in(&home, "../x", "com.foo", &none);
+        fs::remove_dir_all(&home).unwrap();
+
+        let rel = |p: &Path| p.strip_prefix(&lib).unwrap().display().to_string();
+        let planned: Vec<(String, String, bool)> = plan
+            .iter()
+            .map(|m| (rel(&m.from), rel(&m.to), m.blocked.is_some()))
+            .collect();
+        let pair = |a: &str, b: &str, blocked| (a.to_string(), b.to_string(), blocked);
+        assert_eq!(
+            planned,
+            vec![
+                pair(
+                    "Application Support/com.foo.beta",
+                    "Application Support/com.foo",
+                    false
+                ),
+                pair(
+                    "Preferences/com.foo.beta.plist",
+                    "Preferences/com.foo.plist",
+                    false
+                ),
+                pair(
+                    "Preferences/ByHost/com.foo.beta.0000-1111.plist",
+                    "Preferences/ByHost/com.foo.0000-1111.plist",
+                    false
+                ),
+                pair(
+                    &format!("{old}/Data/Documents/notes.txt"),
+                    &format!("{new}/Data/Documents/notes.txt"),
+                    true
+                ),
+                pair(
+                    &format!("{old}/Data/Library/Application Support/com.foo.beta"),
+                    &format!("{new}/Data/Library/Application Support/com.foo"),
+                    false
+                ),
+                pair(
+                    &format!("{old}/Data/Library/Preferences/com.foo.beta.plist"),
+                    &format!("{new}/Data/Library/Preferences/com.foo.plist"),
+                    false
+                ),
+            ]
+        );
+        let container = no_container.last().unwrap();
+        assert_eq!(rel(&container.from), old);
+        assert!(container.blocked.is_some());
+        assert_eq!(report.moved.len(), 5);
+        assert_eq!(report.failed.len(), 1);
+        assert!(matches!(report.failed[0].kind, FailureKind::Refused { .. }));
+        assert_eq!(prefs, "Preferences/com.foo.beta.plist");
+        assert_eq!(
+            inner_prefs,
+            format!("{old}/Data/Library/Preferences/com.foo.beta.plist")
+        );
+        assert!(state && metadata_kept);
+        assert_eq!(notes, format!("{new}/Data/Documents/notes.txt"));
+        assert!(bad_id.is_err());
+    }
+}
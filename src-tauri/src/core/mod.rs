@@ -1,13 +1,72 @@
 //! Pure business logic. No Tauri / UI dependencies.
 
+pub mod access;
 pub mod apps;
+pub mod backup;
+pub mod casks;
+pub mod clean;
+pub mod codesign;
+pub mod duplicates;
+pub mod error;
+pub mod exclusions;
+pub mod inventory;
+pub mod macho;
+pub mod migrate;
+pub mod paths;
 pub mod plist_info;
+pub mod profile;
+pub mod progress;
+pub mod protection;
 pub mod related;
+pub mod reset;
 pub mod running;
+pub mod settings;
+pub mod source;
+pub mod storage;
+pub mod thin;
+pub mod transaction;
 pub mod trash;
+pub mod uninstall;
+pub mod updates;
+pub mod usage;
+pub mod version;
 
-pub use apps::{candidate_app_dirs, compute_size, scan_apps, scan_apps_with_progress};
+pub use access::{check_access, full_disk_access, probe_location};
+pub use apps::{
+    compute_size, compute_size_with_progress, lacks_arm64, read_app, run_scan, scan_apps,
+    scan_apps_with_progress,
+};
+pub use backup::{create_backup, read_manifest, restore_backup};
+pub use casks::{brew_uninstall_zap, cask_for_app, merge_zap_paths, scan_casks, zap_paths};
+pub use clean::{clean_app_data, plan_clean};
+pub use codesign::inspect_app_security;
+pub use duplicates::find_duplicate_apps;
+pub use error::CoreError;
+pub use exclusions::{
+    EXCLUSIONS_FILE, Exclusions, load_exclusions, load_user_exclusions, save_exclusions,
+};
+pub use inventory::{build_inventory, export_inventory, render_inventory};
+pub use migrate::{migrate_app_data, plan_migration};
+pub use paths::{expand_home, user_home};
 pub use plist_info::read_info_from_app;
-pub use related::find_related_paths;
+pub use profile::{load_profile, resolve_profile_for_user};
+pub use progress::{
+    ChannelSink, LogSink, NullSink, ProgressEvent, ProgressKind, ProgressSink, Task, TerminalSink,
+};
+pub use protection::app_protection;
+pub use related::{find_related_paths, run_find_related};
+pub use reset::{ResetOptions, reset_app};
 pub use running::{is_app_running, is_app_running_simple, kill_app};
-pub use trash::{is_protected_path, move_to_trash_or_remove, reveal_in_finder};
+pub use settings::{SETTINGS_FILE, load_settings, save_settings};
+pub use source::install_source;
+pub use storage::{analyze_library, analyze_storage};
+pub use thin::thin_app;
+pub use transaction::{Transaction, preflight};
+pub use trash::{
+    Refusal, RemovalPolicy, is_protected_path, move_to_trash_or_remove, remove_path,
+    reveal_in_finder,
+};
+pub use uninstall::{UninstallOptions, run_uninstall};
+pub use updates::{CurlClient, HttpClient, check_updates};
+pub use usage::is_unused_since;
+pub use version::AppVersion;
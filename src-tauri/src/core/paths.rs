@@ -0,0 +1,19 @@
+//! The current user's home folder and `~`-relative paths.
+
+use home::home_dir;
+use std::path::{Path, PathBuf};
+
+/// The current user's home folder, or `/Users/unknown` when it can't be
+/// determined, so lookups under it find nothing instead of failing.
+pub fn user_home() -> PathBuf {
+    home_dir().unwrap_or_else(|| PathBuf::from("/Users/unknown"))
+}
+
+/// `path` with a leading `~` expanded to `home`.
+pub fn expand_home(path: &str, home: &Path) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => home.join(rest),
+        None if path == "~" => home.to_path_buf(),
+        None => PathBuf::from(path),
+    }
+}
@@ -1,23 +1,35 @@
 //! Read metadata from a `.app` bundle's `Info.plist`.
 
-use anyhow::{Context, Result};
 use plist::Value;
-use std::path::Path;
+use std::{fs, io::Cursor, path::Path};
+
+use super::error::CoreError;
 
 #[derive(Default, Debug)]
 pub struct PlistInfo {
     pub bundle_id: Option<String>,
     pub bundle_name: Option<String>,
+    /// `CFBundleShortVersionString`, falling back to `CFBundleVersion`.
     pub version: Option<String>,
+    /// `CFBundleVersion`, the build number.
+    pub build_version: Option<String>,
     pub executable: Option<String>,
+    /// `SUFeedURL`, the Sparkle appcast the app updates from.
+    pub feed_url: Option<String>,
 }
 
-pub fn read_info_from_app(path: &Path) -> Result<PlistInfo> {
+/// A bundle without an `Info.plist` yields an empty [`PlistInfo`].
+pub fn read_info_from_app(path: &Path) -> Result<PlistInfo, CoreError> {
     let info = path.join("Contents").join("Info.plist");
-    if !info.exists() {
-        return Ok(PlistInfo::default());
-    }
-    let v = Value::from_file(&info).context("Read plist")?;
+    let raw = match fs::read(&info) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(PlistInfo::default()),
+        Err(e) => return Err(CoreError::io(&info, e)),
+    };
+    let v = Value::from_reader(Cursor::new(raw)).map_err(|e| CoreError::Io {
+        path: Some(info.clone()),
+        message: format!("Read plist {}: {e}", info.display()),
+    })?;
     let dict = v.as_dictionary();
     let read = |key: &str| {
         dict.and_then(|d| d.get(key))
@@ -28,6 +40,8 @@ pub fn read_info_from_app(path: &Path) -> Result<PlistInfo> {
         bundle_id: read("CFBundleIdentifier"),
         bundle_name: read("CFBundleName").or_else(|| read("CFBundleDisplayName")),
         version: read("CFBundleShortVersionString").or_else(|| read("CFBundleVersion")),
+        build_version: read("CFBundleVersion"),
         executable: read("CFBundleExecutable"),
+        feed_url: read("SUFeedURL"),
     })
 }
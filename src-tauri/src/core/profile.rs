@@ -0,0 +1,298 @@
+//! Declarative uninstall profiles.
+//!
+//! A profile names the apps to remove and how, so the same cleanup can be
+//! repeated across machines. TOML and JSON share the same keys:
+//!
+//! ```toml
+//! name = "Lab cleanup"
+//! kill_running = true
+//! include = ["~/Library/Application Support/Shared Plugins/*.zoomplugin"]
+//! keep = ["~/Library/Application Support/Foo/license.dat"]
+//!
+//! [[apps]]
+//! bundle_id = "us.zoom.xos"
+//!
+//! [[apps]]
+//! name = "Microsoft *"
+//! ```
+//!
+//! [`resolve_profile`] turns a profile into a [`ProfilePlan`] against the
+//! scanned apps; the caller runs the regular uninstall pipeline per app.
+
+use anyhow::{Context, Result, bail};
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::paths::{expand_home, user_home};
+use crate::models::AppInfo;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UninstallProfile {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub apps: Vec<AppSelector>,
+    /// Extra paths to remove (`~` and globs allowed).
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Paths that must survive. A related path is dropped if it is, contains
+    /// or lies inside a kept path.
+    #[serde(default)]
+    pub keep: Vec<String>,
+    /// Kill running instances before uninstalling. Without it, running apps
+    /// follow the kill policy in settings, as a regular uninstall does.
+    #[serde(default)]
+    pub kill_running: bool,
+}
+
+/// Matches an app by exact bundle id (case-insensitive) and/or a name glob.
+/// When both are given, both must match.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppSelector {
+    #[serde(default)]
+    pub bundle_id: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+impl AppSelector {
+    fn label(&self) -> String {
+        match (&self.bundle_id, &self.name) {
+            (Some(bid), Some(name)) => format!("{bid} ({name})"),
+            (Some(bid), None) => bid.clone(),
+            (None, Some(name)) => name.clone(),
+            (None, None) => String::new(),
+        }
+    }
+
+    pub fn matches(&self, app: &AppInfo) -> bool {
+        let bid_ok = self.bundle_id.as_deref().is_none_or(|want| {
+            app.bundle_id
+                .as_deref()
+                .is_some_and(|bid| bid.eq_ignore_ascii_case(want))
+        });
+        let name_ok = self.name.as_deref().is_none_or(|pattern| {
+            Pattern::new(pattern).is_ok_and(|p| p.matches_with(&app.name, CASE_INSENSITIVE))
+        });
+        (self.bundle_id.is_some() || self.name.is_some()) && bid_ok && name_ok
+    }
+}
+
+const CASE_INSENSITIVE: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: false,
+    require_literal_leading_dot: false,
+};
+
+/// Parse a profile, picking the format from the extension (`.json`, else
+/// TOML).
+pub fn parse_profile(source: &str, path: &Path) -> Result<UninstallProfile> {
+    let profile: UninstallProfile = if path.extension().and_then(|e| e.to_str()) == Some("json") {
+        serde_json::from_str(source).context("Invalid JSON profile")?
+    } else {
+        toml::from_str(source).context("Invalid TOML profile")?
+    };
+    if let Some(empty) = profile
+        .apps
+        .iter()
+        .position(|s| s.bundle_id.is_none() && s.name.is_none())
+    {
+        bail!("App selector #{} needs a bundle_id or name", empty + 1);
+    }
+    Ok(profile)
+}
+
+pub fn load_profile(path: &Path) -> Result<UninstallProfile> {
+    let source =
+        fs::read_to_string(path).with_context(|| format!("Read profile {}", path.display()))?;
+    parse_profile(&source, path)
+}
+
+/// True if removing `candidate` would touch a kept path.
+fn conflicts_with_keep(candidate: &Path, keep: &[Pattern]) -> bool {
+    keep.iter().any(|k| {
+        k.matches_path_with(candidate, CASE_INSENSITIVE)
+            || candidate
+                .ancestors()
+                .skip(1)
+                .any(|a| k.matches_path_with(a, CASE_INSENSITIVE))
+            // `candidate` is an ancestor of a literal kept path.
+            || Path::new(k.as_str()).starts_with(candidate)
+    })
+}
+
+#[derive(Clone, Debug)]
+pub struct PlannedApp {
+    pub app: AppInfo,
+    pub related: Vec<PathBuf>,
+    /// Related paths dropped because of `keep`.
+    pub kept: Vec<PathBuf>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ProfilePlan {
+    pub apps: Vec<PlannedApp>,
+    /// Existing paths from `include`, minus anything kept.
+    pub extra_paths: Vec<PathBuf>,
+    /// Selectors that matched no installed app.
+    pub unmatched: Vec<String>,
+    pub kill_running: bool,
+}
+
+/// Resolve `profile` against `apps`. `related` finds an app's related
+/// paths (normally `find_related_paths` plus cask zap paths); `home`
+/// expands `~`.
+pub fn resolve_profile<F>(
+    profile: &UninstallProfile,
+    apps: &[AppInfo],
+    home: &Path,
+    mut related: F,
+) -> ProfilePlan
+where
+    F: FnMut(&AppInfo) -> Vec<PathBuf>,
+{
+    let keep: Vec<Pattern> = profile
+        .keep
+        .iter()
+        .filter_map(|k| Pattern::new(&expand_home(k, home).to_string_lossy()).ok())
+        .collect();
+
+    let mut plan = ProfilePlan {
+        kill_running: profile.kill_running,
+        ..Default::default()
+    };
+    for selector in &profile.apps {
+        let mut matched = false;
+        for app in apps.iter().filter(|a| selector.matches(a)) {
+            matched = true;
+            if plan.apps.iter().any(|p| p.app.path == app.path) {
+                continue;
+            }
+            let (kept, related): (Vec<PathBuf>, Vec<PathBuf>) = related(app)
+                .into_iter()
+                .partition(|p| conflicts_with_keep(p, &keep));
+            plan.apps.push(PlannedApp {
+                app: app.clone(),
+                related,
+                kept,
+            });
+        }
+        if !matched {
+            plan.unmatched.push(selector.label());
+        }
+    }
+
+    for entry in &profile.include {
+        let pattern = expand_home(entry, home);
+        let found: Vec<PathBuf> = glob::glob(&pattern.to_string_lossy())
+            .map(|paths| paths.flatten().collect())
+            .unwrap_or_default();
+        plan.extra_paths.extend(
+            found
+                .into_iter()
+                .filter(|p| p.is_absolute() && !conflicts_with_keep(p, &keep)),
+        );
+    }
+    plan.extra_paths.sort();
+    plan.extra_paths.dedup();
+    plan
+}
+
+/// Resolve against the current user's home directory.
+pub fn resolve_profile_for_user<F>(
+    profile: &UninstallProfile,
+    apps: &[AppInfo],
+    related: F,
+) -> ProfilePlan
+where
+    F: FnMut(&AppInfo) -> Vec<PathBuf>,
+{
+    let home = user_home();
+    resolve_profile(profile, apps, &home, related)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::InstallSource;
+
+    fn app(name: &str, bid: &str) -> AppInfo {
+        AppInfo {
+            path: PathBuf::from(format!("/Applications/{name}.app")),
+            name: name.to_string(),
+            bundle_id: Some(bid.to_string()),
+            install_source: InstallSource::DragAndDrop,
+            ..Default::default()
+        }
+    }
+
+    const PROFILE: &str = r#"
+name = "Lab cleanup"
+kill_running = true
+keep = ["~/Library/Application Support/Microsoft/license.dat", "~/Library/Caches/*.keep"]
+
+[[apps]]
+bundle_id = "US.ZOOM.XOS"
+
+[[apps]]
+name = "microsoft *"
+
+[[apps]]
+name = "Nonexistent"
+"#;
+
+    #[test]
+    fn parses_toml_and_json() {
+        let toml = parse_profile(PROFILE, Path::new("p.toml")).unwrap();
+        assert_eq!(toml.name.as_deref(), Some("Lab cleanup"));
+        assert_eq!(toml.apps.len(), 3);
+        assert!(toml.kill_running);
+
+        let json = parse_profile(
+            r#"{"apps": [{"bundle_id": "us.zoom.xos"}], "include": ["/tmp/x"]}"#,
+            Path::new("p.json"),
+        )
+        .unwrap();
+        assert_eq!(json.apps[0].bundle_id.as_deref(), Some("us.zoom.xos"));
+        assert!(!json.kill_running);
+
+        assert!(parse_profile("[[apps]]\n", Path::new("p.toml")).is_err());
+    }
+
+    #[test]
+    fn resolves_selectors_and_keep_rules() {
+        let profile = parse_profile(PROFILE, Path::new("p.toml")).unwrap();
+        let apps = vec![
+            app("zoom.us", "us.zoom.xos"),
+            app("Microsoft Word", "com.microsoft.Word"),
+            app("Slack", "com.tinyspeck.slackmacgap"),
+        ];
+        let home = Path::new("/Users/a");
+        let plan = resolve_profile(&profile, &apps, home, |a| {
+            let lib = home.join("Library");
+            if a.name == "Microsoft Word" {
+                vec![
+                    lib.join("Application Support/Microsoft"),
+                    lib.join("Containers/com.microsoft.Word"),
+                    lib.join("Caches/word.keep"),
+                ]
+            } else {
+                vec![lib.join("Caches/us.zoom.xos")]
+            }
+        });
+
+        let names: Vec<&str> = plan.apps.iter().map(|p| p.app.name.as_str()).collect();
+        assert_eq!(names, vec!["zoom.us", "Microsoft Word"]);
+        assert_eq!(
+            plan.apps[1].related,
+            vec![home.join("Library/Containers/com.microsoft.Word")]
+        );
+        assert_eq!(plan.apps[1].kept.len(), 2);
+        assert_eq!(plan.unmatched, vec!["Nonexistent"]);
+        assert!(plan.kill_running);
+    }
+}
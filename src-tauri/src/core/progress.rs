@@ -0,0 +1,313 @@
+//! Task progress, independent of any UI.
+//!
+//! Long-running operations start a [`Task`] against a [`ProgressSink`].
+//! Each [`ProgressEvent`] carries the task's id and a per-task sequence
+//! number, so a consumer can track several tasks at once and ignore events
+//! that arrive out of order. The Tauri app forwards events to the frontend
+//! (`crate::progress`); the CLI draws a [`TerminalSink`]; tests collect them
+//! through a [`ChannelSink`].
+
+use serde::Serialize;
+use std::{
+    io::{IsTerminal, Write},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+        mpsc::{Receiver, Sender, channel},
+    },
+    time::Instant,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressKind {
+    RefreshApps,
+    FindRelated,
+    Uninstall,
+    CheckUpdates,
+    SizeComputation,
+    StorageAnalysis,
+    CleanData,
+    ResetApp,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressEvent {
+    pub task_id: u64,
+    /// 0 for the first event of a task, then +1 per event.
+    pub seq: u64,
+    pub kind: ProgressKind,
+    pub progress: f32,
+    pub message: String,
+    pub finished: bool,
+    pub error: Option<String>,
+    /// Items processed so far, for tasks that count items.
+    pub done: Option<u64>,
+    /// Item count, when known up front.
+    pub total: Option<u64>,
+    /// Bytes processed so far, for tasks that measure data.
+    pub bytes: Option<u64>,
+    pub elapsed_ms: u64,
+    /// Linear extrapolation from `progress` and `elapsed_ms`. `None` before
+    /// there is progress to extrapolate from, and once finished.
+    pub eta_ms: Option<u64>,
+}
+
+/// Receives every event of every task started against it.
+pub trait ProgressSink: Send + Sync {
+    fn emit(&self, event: ProgressEvent);
+}
+
+/// Discards events.
+pub struct NullSink;
+
+impl ProgressSink for NullSink {
+    fn emit(&self, _event: ProgressEvent) {}
+}
+
+/// Writes events to the `log` facade: intermediate updates at debug level,
+/// completions at info, errors at warn.
+pub struct LogSink;
+
+impl ProgressSink for LogSink {
+    fn emit(&self, e: ProgressEvent) {
+        let kind = serde_json::to_value(e.kind).unwrap_or_default();
+        let kind = kind.as_str().unwrap_or_default();
+        match (&e.error, e.finished) {
+            (Some(err), _) => log::warn!("[{kind}#{}] {}: {err}", e.task_id, e.message),
+            (None, true) => log::info!("[{kind}#{}] {}", e.task_id, e.message),
+            (None, false) => log::debug!(
+                "[{kind}#{}] {:>3.0}% {}",
+                e.task_id,
+                e.progress * 100.0,
+                e.message
+            ),
+        }
+    }
+}
+
+/// Sends events down an mpsc channel.
+pub struct ChannelSink(Sender<ProgressEvent>);
+
+impl ChannelSink {
+    pub fn new() -> (Self, Receiver<ProgressEvent>) {
+        let (tx, rx) = channel();
+        (Self(tx), rx)
+    }
+}
+
+impl ProgressSink for ChannelSink {
+    fn emit(&self, event: ProgressEvent) {
+        let _ = self.0.send(event);
+    }
+}
+
+/// Redraws a one-line progress bar on stderr. Does nothing when stderr is
+/// not a terminal, so piped CLI output stays clean.
+pub struct TerminalSink {
+    enabled: bool,
+}
+
+impl Default for TerminalSink {
+    fn default() -> Self {
+        Self {
+            enabled: std::io::stderr().is_terminal(),
+        }
+    }
+}
+
+const BAR_WIDTH: usize = 24;
+
+/// `[#########---------------]  37% message`, message cut to fit 80 columns.
+pub fn render_bar(progress: f32, message: &str) -> String {
+    let filled = ((progress.clamp(0.0, 1.0) * BAR_WIDTH as f32).round() as usize).min(BAR_WIDTH);
+    let message: String = message.chars().take(80 - BAR_WIDTH - 8).collect();
+    format!(
+        "[{}{}] {:>3.0}% {message}",
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH - filled),
+        progress * 100.0
+    )
+}
+
+impl ProgressSink for TerminalSink {
+    fn emit(&self, e: ProgressEvent) {
+        if !self.enabled {
+            return;
+        }
+        let mut err = std::io::stderr().lock();
+        // `\x1b[2K` clears the previous, possibly longer, line.
+        let _ = write!(err, "\r\x1b[2K{}", render_bar(e.progress, &e.message));
+        if e.finished {
+            match &e.error {
+                Some(error) => {
+                    let _ = writeln!(err, "\n{error}");
+                }
+                None => {
+                    let _ = writeln!(err);
+                }
+            }
+        }
+        let _ = err.flush();
+    }
+}
+
+static NEXT_TASK_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Default)]
+struct TaskState {
+    seq: u64,
+    progress: f32,
+    done: Option<u64>,
+    total: Option<u64>,
+    bytes: Option<u64>,
+}
+
+/// One running task. Methods take `&self`, so closures reporting from a
+/// callback can share it.
+pub struct Task<'a> {
+    sink: &'a dyn ProgressSink,
+    id: u64,
+    kind: ProgressKind,
+    started: Instant,
+    state: Mutex<TaskState>,
+}
+
+fn eta_ms(elapsed_ms: u64, progress: f32) -> Option<u64> {
+    (progress > 0.0 && progress < 1.0)
+        .then(|| (elapsed_ms as f64 * f64::from(1.0 - progress) / f64::from(progress)) as u64)
+}
+
+impl<'a> Task<'a> {
+    /// Allocate a task id and emit its first event.
+    pub fn start(
+        sink: &'a dyn ProgressSink,
+        kind: ProgressKind,
+        message: impl Into<String>,
+    ) -> Self {
+        let task = Self {
+            sink,
+            id: NEXT_TASK_ID.fetch_add(1, Ordering::Relaxed),
+            kind,
+            started: Instant::now(),
+            state: Mutex::new(TaskState::default()),
+        };
+        task.report(0.0, message, None);
+        task
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Set the item counters reported with subsequent events.
+    pub fn set_items(&self, done: u64, total: Option<u64>) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.done = Some(done);
+        state.total = total;
+    }
+
+    /// Set the byte counter reported with subsequent events.
+    pub fn set_bytes(&self, bytes: u64) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.bytes = Some(bytes);
+    }
+
+    pub fn progress(&self, progress: f32, message: impl Into<String>) {
+        self.report(progress, message, None);
+    }
+
+    /// A running update that also reports a per-item error.
+    pub fn report(&self, progress: f32, message: impl Into<String>, error: Option<String>) {
+        self.send(Some(progress), message.into(), false, error);
+    }
+
+    pub fn finish(&self, message: impl Into<String>) {
+        self.send(Some(1.0), message.into(), true, None);
+    }
+
+    /// Finish with an error, keeping the last reported progress.
+    pub fn fail(&self, message: impl Into<String>, error: impl Into<String>) {
+        self.send(None, message.into(), true, Some(error.into()));
+    }
+
+    fn send(&self, progress: Option<f32>, message: String, finished: bool, error: Option<String>) {
+        // Emit while holding the lock so `seq` order is delivery order even
+        // when callbacks report from several threads.
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(p) = progress {
+            state.progress = p.clamp(0.0, 1.0);
+        }
+        let elapsed_ms = self.started.elapsed().as_millis() as u64;
+        let event = ProgressEvent {
+            task_id: self.id,
+            seq: state.seq,
+            kind: self.kind,
+            progress: state.progress,
+            message,
+            finished,
+            error,
+            done: state.done,
+            total: state.total,
+            bytes: state.bytes,
+            elapsed_ms,
+            eta_ms: if finished {
+                None
+            } else {
+                eta_ms(elapsed_ms, state.progress)
+            },
+        };
+        state.seq += 1;
+        self.sink.emit(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_events_per_task() {
+        let (sink, rx) = ChannelSink::new();
+        let a = Task::start(&sink, ProgressKind::FindRelated, "a");
+        let b = Task::start(&sink, ProgressKind::SizeComputation, "b");
+        a.set_items(1, Some(4));
+        a.progress(0.25, "a1");
+        b.set_bytes(4096);
+        b.finish("b done");
+        a.fail("a failed", "boom");
+
+        let events: Vec<ProgressEvent> = rx.try_iter().collect();
+        let summary: Vec<(u64, &str, f32, bool)> = events
+            .iter()
+            .map(|e| (e.seq, e.message.as_str(), e.progress, e.finished))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (0, "a", 0.0, false),
+                (0, "b", 0.0, false),
+                (1, "a1", 0.25, false),
+                (1, "b done", 1.0, true),
+                (2, "a failed", 0.25, true),
+            ]
+        );
+        assert_ne!(events[0].task_id, events[1].task_id);
+        assert_eq!((events[2].done, events[2].total), (Some(1), Some(4)));
+        assert_eq!(events[3].bytes, Some(4096));
+        assert_eq!(events[4].error.as_deref(), Some("boom"));
+        assert_eq!(events[4].eta_ms, None);
+    }
+
+    #[test]
+    fn extrapolates_eta_and_draws_bar() {
+        assert_eq!(eta_ms(1000, 0.25), Some(3000));
+        assert_eq!(eta_ms(1000, 0.0), None);
+        assert_eq!(eta_ms(1000, 1.0), None);
+        assert_eq!(
+            render_bar(0.5, "Scanning"),
+            "[############------------]  50% Scanning"
+        );
+    }
+}
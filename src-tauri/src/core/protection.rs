@@ -0,0 +1,124 @@
+//! Classify apps that must not be uninstalled casually.
+//!
+//! Two signals mark an app as Apple's own: its bundle lives on the sealed
+//! system volume (`/System/Applications`, or `/Applications/Safari.app`
+//! which is a symlink into a cryptex under `/System`), or its bundle id is
+//! `com.apple.*` *and* its code signature chains to Apple's own signing
+//! authorities. The signature check keeps a third-party app that merely
+//! claims an Apple bundle id from being shielded.
+
+use anyhow::Result;
+use std::path::Path;
+
+use super::codesign::{is_apple_signed, read_signing_authorities};
+use crate::models::AppProtection;
+
+/// Determine the [`AppProtection`] of the bundle at `path`. Reads the code
+/// signature only for `com.apple.*` bundle ids, so it stays cheap enough to
+/// run for every app during `scan_apps`.
+pub fn app_protection(
+    path: &Path,
+    bundle_id: Option<&str>,
+    executable: Option<&str>,
+) -> AppProtection {
+    app_protection_with(path, bundle_id, executable, read_signing_authorities)
+}
+
+/// [`app_protection`] with the signing authorities of an executable read by
+/// `read_authorities`.
+fn app_protection_with(
+    path: &Path,
+    bundle_id: Option<&str>,
+    executable: Option<&str>,
+    read_authorities: impl FnOnce(&Path) -> Result<Option<Vec<String>>>,
+) -> AppProtection {
+    let resolved = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if resolved.starts_with("/System") || path.starts_with("/System") {
+        return AppProtection::SealedSystem;
+    }
+
+    let claims_apple = bundle_id.is_some_and(|b| b.to_lowercase().starts_with("com.apple."));
+    if !claims_apple {
+        return AppProtection::None;
+    }
+
+    let Some(exe) = executable else {
+        return AppProtection::Apple;
+    };
+    match read_authorities(&path.join("Contents").join("MacOS").join(exe)) {
+        Ok(Some(chain)) if is_apple_signed(&chain) => AppProtection::Apple,
+        Ok(Some(_)) | Ok(None) => AppProtection::None,
+        // Unreadable signature: err on the side of protecting it.
+        Err(_) => AppProtection::Apple,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(authorities: &[&str]) -> Result<Option<Vec<String>>> {
+        Ok(Some(authorities.iter().map(|a| a.to_string()).collect()))
+    }
+
+    fn protection(
+        path: &str,
+        bundle_id: Option<&str>,
+        authorities: Result<Option<Vec<String>>>,
+    ) -> AppProtection {
+        let mut read = Some(authorities);
+        app_protection_with(Path::new(path), bundle_id, Some("App"), |exe| {
+            assert!(exe.ends_with("Contents/MacOS/App"));
+            read.take().unwrap()
+        })
+    }
+
+    #[test]
+    fn classifies_each_signal() {
+        let apple = || chain(&["Software Signing", "Apple Code Signing CA", "Apple Root CA"]);
+        let developer = || {
+            chain(&[
+                "Developer ID Application: Foo (ABCDE12345)",
+                "Developer ID Certification Authority",
+                "Apple Root CA",
+            ])
+        };
+
+        assert_eq!(
+            protection(
+                "/System/Applications/Mail.app",
+                Some("com.apple.mail"),
+                apple()
+            ),
+            AppProtection::SealedSystem
+        );
+        assert_eq!(
+            protection(
+                "/Applications/Xcode.app",
+                Some("com.apple.dt.Xcode"),
+                apple()
+            ),
+            AppProtection::Apple
+        );
+        assert_eq!(
+            protection(
+                "/Applications/Fake.app",
+                Some("com.apple.fake"),
+                developer()
+            ),
+            AppProtection::None
+        );
+        assert_eq!(
+            protection(
+                "/Applications/Odd.app",
+                Some("com.apple.odd"),
+                Err(anyhow::anyhow!("truncated signature"))
+            ),
+            AppProtection::Apple
+        );
+        assert_eq!(
+            protection("/Applications/Foo.app", Some("com.foo"), apple()),
+            AppProtection::None
+        );
+    }
+}
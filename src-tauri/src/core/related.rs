@@ -5,9 +5,23 @@ use home::home_dir;
 use std::path::PathBuf;
 use walkdir::WalkDir;
 
-pub fn find_related_paths(bundle_id: Option<&str>, app_name: Option<&str>) -> Vec<PathBuf> {
+use super::{
+    casks::{merge_zap_paths, scan_casks},
+    exclusions::Exclusions,
+    paths::user_home,
+    progress::{ProgressKind, ProgressSink, Task},
+};
+
+/// `max_depth` bounds the walk below each Library folder (1 = direct
+/// children). Paths matching `exclusions` are never returned.
+pub fn find_related_paths(
+    bundle_id: Option<&str>,
+    app_name: Option<&str>,
+    max_depth: usize,
+    exclusions: &Exclusions,
+) -> Vec<PathBuf> {
     let mut res: Vec<PathBuf> = Vec::new();
-    let home = home_dir().unwrap_or_else(|| PathBuf::from("/Users/unknown"));
+    let home = user_home();
 
     if let Some(bid) = bundle_id {
         res.extend(common_paths_for_bundle_id(bid));
@@ -27,7 +41,7 @@ pub fn find_related_paths(bundle_id: Option<&str>, app_name: Option<&str>) -> Ve
         for lib in libs {
             if lib.exists() && lib.is_dir() {
                 for ent in WalkDir::new(&lib)
-                    .max_depth(2)
+                    .max_depth(max_depth)
                     .min_depth(1)
                     .into_iter()
                     .flatten()
@@ -49,10 +63,35 @@ pub fn find_related_paths(bundle_id: Option<&str>, app_name: Option<&str>) -> Ve
 
     res.sort();
     res.dedup();
-    res.retain(|p| p.exists());
+    res.retain(|p| p.exists() && !exclusions.is_excluded(p));
     res
 }
 
+/// [`find_related_paths`] plus, when `cask` is set, the cask's `zap` paths,
+/// as a `find_related` task reported to `sink`.
+pub fn run_find_related(
+    bundle_id: Option<&str>,
+    app_name: &str,
+    cask: Option<&str>,
+    max_depth: usize,
+    exclusions: &Exclusions,
+    sink: &dyn ProgressSink,
+) -> Vec<PathBuf> {
+    let task = Task::start(
+        sink,
+        ProgressKind::FindRelated,
+        format!("Finding related files for {app_name}..."),
+    );
+    let mut paths = find_related_paths(bundle_id, Some(app_name), max_depth, exclusions);
+    if cask.is_some() {
+        merge_zap_paths(&scan_casks(), cask, &mut paths);
+        paths.retain(|p| !exclusions.is_excluded(p));
+    }
+    task.set_items(paths.len() as u64, Some(paths.len() as u64));
+    task.finish(format!("Found {} related item(s).", paths.len()));
+    paths
+}
+
 fn common_paths_for_bundle_id(bid: &str) -> Vec<PathBuf> {
     let mut v = Vec::new();
     if let Some(h) = home_dir() {
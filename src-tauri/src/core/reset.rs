@@ -0,0 +1,457 @@
+//! Reset an app to its first-launch state without reinstalling it.
+//!
+//! Apple's own apps are refused. Others are force-quit, then their
+//! preferences, sandbox container, saved window state and Application
+//! Support data are removed. The bundle stays, and so do caches
+//! (harmless, and rebuilt anyway) and group containers, which are shared
+//! with sibling apps of the same vendor.
+
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
+
+use super::{
+    apps::read_app,
+    backup::create_backup,
+    error::CoreError,
+    exclusions::Exclusions,
+    paths::user_home,
+    plist_info::read_info_from_app,
+    progress::{ProgressKind, ProgressSink, Task},
+    protection::app_protection,
+    related::find_related_paths,
+    running::{is_app_running_simple, kill_app},
+    uninstall::remove_partitioned,
+};
+use crate::models::{
+    AppProtection, BackupFormat, FailureKind, RemovalMode, ResetReport, Settings, UninstallFailure,
+    UninstallReport,
+};
+
+/// Per-call switches for [`reset_app`].
+#[derive(Clone, Debug)]
+pub struct ResetOptions {
+    pub removal_mode: RemovalMode,
+    pub kill_deadline: Duration,
+    pub related_max_depth: usize,
+    /// Archive everything before removing it, into this folder.
+    pub backup_dir: Option<PathBuf>,
+    pub backup_format: BackupFormat,
+}
+
+impl ResetOptions {
+    /// Options from the user's settings, without a backup.
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            removal_mode: settings.removal_mode,
+            kill_deadline: settings.kill_deadline(),
+            related_max_depth: settings.related_max_depth,
+            backup_dir: None,
+            backup_format: settings.backup_format,
+        }
+    }
+}
+
+/// Whether `path` is app state a reset removes: an entry of a user or
+/// system Library's Preferences, Containers, Saved Application State or
+/// Application Support folder, never the folder itself.
+fn is_reset_target(path: &Path, home: &Path) -> bool {
+    [home.join("Library"), PathBuf::from("/Library")]
+        .iter()
+        .filter_map(|lib| path.strip_prefix(lib).ok())
+        .any(|rest| {
+            let names: Vec<&str> = rest
+                .components()
+                .filter_map(|c| match c {
+                    Component::Normal(s) => s.to_str(),
+                    _ => None,
+                })
+                .collect();
+            match names.as_slice() {
+                ["Preferences", "ByHost"] => false,
+                [
+                    "Preferences"
+                    | "Containers"
+                    | "Saved Application State"
+                    | "Application Support",
+                    _,
+                    ..,
+                ] => true,
+                _ => false,
+            }
+        })
+}
+
+/// Per-bundle locations `find_related_paths` may not reach.
+fn known_paths(home: &Path, bundle_id: Option<&str>, app_name: &str) -> Vec<PathBuf> {
+    let lib = home.join("Library");
+    let mut v = vec![lib.join("Application Support").join(app_name)];
+    if let Some(bid) = bundle_id {
+        v.extend([
+            lib.join("Preferences").join(format!("{bid}.plist")),
+            lib.join("Containers").join(bid),
+            lib.join("Saved Application State")
+                .join(format!("{bid}.savedState")),
+            lib.join("Application Support").join(bid),
+        ]);
+        // Per-machine preferences: `ByHost/<bundle id>.<hardware UUID>.plist`.
+        if let Ok(by_host) = fs::read_dir(lib.join("Preferences").join("ByHost")) {
+            let prefix = format!("{bid}.");
+            v.extend(
+                by_host
+                    .flatten()
+                    .filter(|e| e.file_name().to_string_lossy().starts_with(&prefix))
+                    .map(|e| e.path()),
+            );
+        }
+    }
+    v
+}
+
+/// What a reset of the app removes, given its related paths. Missing paths
+/// and paths inside another target are dropped.
+pub fn reset_targets_in(
+    home: &Path,
+    bundle_id: Option<&str>,
+    app_name: &str,
+    related: Vec<PathBuf>,
+) -> Vec<PathBuf> {
+    let mut paths = related;
+    paths.extend(known_paths(home, bundle_id, app_name));
+    paths.sort();
+    paths.dedup();
+    let mut targets: Vec<PathBuf> = Vec::new();
+    for p in paths {
+        // Sorted, so an ancestor always comes before its descendants.
+        if targets.iter().any(|t| p.starts_with(t)) {
+            continue;
+        }
+        if is_reset_target(&p, home) && fs::symlink_metadata(&p).is_ok() {
+            targets.push(p);
+        }
+    }
+    targets
+}
+
+/// `cfprefsd` keeps preferences cached and would write them back; drop its
+/// copy too.
+fn flush_preferences(bundle_id: &str) {
+    if let Err(e) = Command::new("defaults")
+        .args(["delete", bundle_id])
+        .output()
+    {
+        log::debug!("defaults delete {bundle_id}: {e}");
+    }
+}
+
+/// The parts of a reset that look at or act on the live system.
+struct ResetHost<'a> {
+    home: PathBuf,
+    protection: &'a dyn Fn() -> AppProtection,
+    /// Quit the app, returning how many processes were killed.
+    kill: &'a dyn Fn() -> u32,
+    is_running: &'a dyn Fn() -> bool,
+    related: &'a dyn Fn() -> Vec<PathBuf>,
+    flush_preferences: &'a dyn Fn(&str),
+}
+
+/// Refuse Apple and sealed-volume apps before touching them, then quit the
+/// app with [`kill_app`], optionally back up its state, and remove it with
+/// the same protected/unprotected handling as `run_uninstall`. Reported
+/// to `sink` as a `reset_app` task.
+pub fn reset_app(
+    app_path: PathBuf,
+    app_name: String,
+    bundle_id: Option<String>,
+    options: ResetOptions,
+    exclusions: &Exclusions,
+    sink: &dyn ProgressSink,
+) -> Result<ResetReport, CoreError> {
+    let (path, name, bid) = (&app_path, &app_name, bundle_id.as_deref());
+    let host = ResetHost {
+        home: user_home(),
+        protection: &|| {
+            let executable = read_info_from_app(path).ok().and_then(|i| i.executable);
+            app_protection(path, bid, executable.as_deref())
+        },
+        kill: &|| kill_app(Some(path), bid, Some(name), options.kill_deadline),
+        is_running: &|| is_app_running_simple(Some(path), bid, Some(name)),
+        related: &|| find_related_paths(bid, Some(name), options.related_max_depth, exclusions),
+        flush_preferences: &flush_preferences,
+    };
+    reset_app_with(
+        &host,
+        app_path.clone(),
+        app_name.clone(),
+        bundle_id.clone(),
+        &options,
+        exclusions,
+        sink,
+    )
+}
+
+/// [`reset_app`] against `host` instead of the live system.
+fn reset_app_with(
+    host: &ResetHost,
+    app_path: PathBuf,
+    app_name: String,
+    bundle_id: Option<String>,
+    options: &ResetOptions,
+    exclusions: &Exclusions,
+    sink: &dyn ProgressSink,
+) -> Result<ResetReport, CoreError> {
+    let task = Task::start(
+        sink,
+        ProgressKind::ResetApp,
+        format!("Resetting {app_name}..."),
+    );
+    let abort = |err: CoreError| {
+        task.fail(err.to_string(), err.to_string());
+        Err(err)
+    };
+
+    let reason = match (host.protection)() {
+        AppProtection::None => None,
+        AppProtection::Apple => Some(format!("{app_name} is an Apple app. Abort reset.")),
+        AppProtection::SealedSystem => {
+            Some(format!("{app_name} is part of macOS and cannot be reset."))
+        }
+    };
+    if let Some(reason) = reason {
+        return abort(CoreError::Protected {
+            path: Some(app_path),
+            reason,
+        });
+    }
+
+    let killed = (host.kill)();
+    if killed > 0 {
+        task.progress(0.0, format!("Quit {app_name}."));
+    }
+    if (host.is_running)() {
+        return abort(CoreError::AppRunning {
+            name: app_name,
+            path: Some(app_path),
+        });
+    }
+
+    let home = &host.home;
+    let related = (host.related)();
+    let (excluded, targets): (Vec<PathBuf>, Vec<PathBuf>) =
+        reset_targets_in(home, bundle_id.as_deref(), &app_name, related)
+            .into_iter()
+            .partition(|p| exclusions.is_excluded(p));
+
+    let mut report = UninstallReport {
+        app_path,
+        removed: Vec::new(),
+        failed: Vec::new(),
+        aborted: false,
+        backup: None,
+    };
+    for p in excluded {
+        let reason = format!(
+            "Excluded by rule {:?}",
+            exclusions.matching_rule(&p).unwrap_or_default()
+        );
+        report.failed.push(UninstallFailure {
+            path: p,
+            error: reason.clone(),
+            kind: FailureKind::Refused { reason },
+        });
+    }
+
+    if let Some(dir) = options.backup_dir.as_ref().filter(|_| !targets.is_empty()) {
+        task.progress(0.0, format!("Backing up {} item(s)...", targets.len()));
+        let label = format!("{app_name} reset");
+        let app = read_app(&report.app_path);
+        match create_backup(dir, &label, &targets, Some(&app), options.backup_format) {
+            Ok(archive) => report.backup = Some(archive),
+            Err(e) => return abort(CoreError::from(e)),
+        }
+    }
+
+    let total = targets.len();
+    let mut done = 0usize;
+    remove_partitioned(
+        targets,
+        options.removal_mode,
+        &mut report,
+        &task,
+        |message, error| {
+            done += 1;
+            task.set_items(done as u64, Some(total as u64));
+            task.report(done as f32 / total as f32, message, error);
+        },
+    )?;
+
+    let prefs = home.join("Library").join("Preferences");
+    if let Some(bid) = &bundle_id {
+        if report.removed.iter().any(|p| p.starts_with(&prefs)) {
+            (host.flush_preferences)(bid);
+        }
+    }
+
+    task.finish("Reset complete");
+    Ok(ResetReport { report, killed })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{backup::read_manifest, progress::ChannelSink};
+    use crate::models::ExclusionRules;
+    use std::cell::{Cell, RefCell};
+
+    #[test]
+    fn selects_app_state_only() {
+        let home = std::env::temp_dir().join(format!("reset-test-{}", std::process::id()));
+        let lib = home.join("Library");
+        let write = |rel: &str| {
+            let p = lib.join(rel);
+            fs::create_dir_all(p.parent().unwrap()).unwrap();
+            fs::write(p, "x").unwrap();
+        };
+        for rel in [
+            "Preferences/com.foo.plist",
+            "Preferences/ByHost/com.foo.0000-1111.plist",
+            "Preferences/ByHost/com.foobar.0000-1111.plist",
+            "Containers/com.foo/Data/Library/Preferences/com.foo.plist",
+            "Group Containers/group.com.foo/shared.db",
+            "Saved Application State/com.foo.savedState/windows.plist",
+            "Application Support/Foo/db.sqlite",
+            "Caches/com.foo/Cache.db",
+        ] {
+            write(rel);
+        }
+        let related = vec![
+            lib.join("Caches").join("com.foo"),
+            lib.join("Group Containers").join("group.com.foo"),
+            lib.join("Containers").join("com.foo").join("Data"),
+            lib.join("Preferences").join("ByHost"),
+        ];
+
+        let targets = reset_targets_in(&home, Some("com.foo"), "Foo", related);
+        fs::remove_dir_all(&home).unwrap();
+
+        let rel: Vec<String> = targets
+            .iter()
+            .map(|p| p.strip_prefix(&lib).unwrap().display().to_string())
+            .collect();
+        assert_eq!(
+            rel,
+            vec![
+                "Application Support/Foo",
+                "Containers/com.foo",
+                "Preferences/ByHost/com.foo.0000-1111.plist",
+                "Preferences/com.foo.plist",
+                "Saved Application State/com.foo.savedState",
+            ]
+        );
+    }
+
+    #[test]
+    fn resets_through_host() {
+        let home = std::env::temp_dir().join(format!("reset-app-test-{}", std::process::id()));
+        let lib = home.join("Library");
+        let prefs = lib.join("Preferences").join("com.foo.plist");
+        let support = lib.join("Application Support").join("Foo");
+        let state = lib
+            .join("Saved Application State")
+            .join("com.foo.savedState");
+        for dir in [prefs.parent().unwrap(), &support, &state] {
+            fs::create_dir_all(dir).unwrap();
+        }
+        fs::write(&prefs, "plist").unwrap();
+        fs::write(support.join("db.sqlite"), "data").unwrap();
+        let rules = ExclusionRules {
+            paths: vec![state.to_string_lossy().into_owned()],
+            ..Default::default()
+        };
+        let exclusions = Exclusions::compile(&rules, &home).unwrap();
+        let options = ResetOptions {
+            removal_mode: RemovalMode::Delete,
+            backup_dir: Some(home.join("backups")),
+            ..ResetOptions::from_settings(&Settings::default())
+        };
+        let protection = Cell::new(AppProtection::Apple);
+        let running = Cell::new(true);
+        let kills = Cell::new(0);
+        let flushed = RefCell::new(Vec::new());
+        let host = ResetHost {
+            home: home.clone(),
+            protection: &|| protection.get(),
+            kill: &|| {
+                kills.set(kills.get() + 1);
+                1
+            },
+            is_running: &|| running.get(),
+            related: &Vec::new,
+            flush_preferences: &|bid| flushed.borrow_mut().push(bid.to_string()),
+        };
+        let reset = || {
+            let (sink, rx) = ChannelSink::new();
+            let app = home.join("Foo.app");
+            let result = reset_app_with(
+                &host,
+                app,
+                "Foo".into(),
+                Some("com.foo".into()),
+                &options,
+                &exclusions,
+                &sink,
+            );
+            let last = rx.try_iter().last().unwrap();
+            (result, last.finished, last.error)
+        };
+
+        let (protected, finished, error) = reset();
+        assert!(matches!(protected, Err(CoreError::Protected { .. })));
+        assert!(finished && error.is_some());
+        assert_eq!(kills.get(), 0, "never quit");
+
+        protection.set(AppProtection::None);
+        let (still_running, finished, error) = reset();
+        let untouched = prefs.exists() && support.exists();
+        assert!(matches!(still_running, Err(CoreError::AppRunning { .. })));
+        assert!(finished && error.is_some());
+        assert!(untouched);
+        assert!(flushed.borrow().is_empty());
+
+        running.set(false);
+        let (first, finished, error) = reset();
+        let first = first.unwrap();
+        let manifest = read_manifest(first.report.backup.as_ref().unwrap()).unwrap();
+        let state_kept = state.exists();
+        let flushed_after_first = flushed.borrow().clone();
+        // Only Application Support is back; the preferences stay gone.
+        fs::create_dir_all(&support).unwrap();
+        let (second, _, _) = reset();
+        let second = second.unwrap();
+        fs::remove_dir_all(&home).unwrap();
+
+        assert!(finished && error.is_none());
+        assert_eq!(first.killed, 1);
+        assert_eq!(first.report.removed, vec![support.clone(), prefs.clone()]);
+        let archived: Vec<_> = manifest
+            .entries
+            .iter()
+            .map(|e| e.original.clone())
+            .collect();
+        assert_eq!(archived, vec![support.clone(), prefs]);
+        assert_eq!(manifest.app.unwrap().path, home.join("Foo.app"));
+        assert_eq!(first.report.failed.len(), 1);
+        assert_eq!(first.report.failed[0].path, state);
+        assert!(matches!(
+            first.report.failed[0].kind,
+            FailureKind::Refused { .. }
+        ));
+        assert!(state_kept);
+        assert_eq!(flushed_after_first, vec!["com.foo"]);
+        assert_eq!(second.report.removed, vec![support]);
+        assert_eq!(flushed.into_inner(), vec!["com.foo"], "no prefs removed");
+    }
+}
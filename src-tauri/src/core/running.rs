@@ -192,17 +192,19 @@ pub fn is_app_running_simple(
     is_app_running(&sys, bundle_path, bundle_id, app_name, None)
 }
 
-/// Send SIGKILL to every process that matches the given app, then wait (with a
-/// short timeout) for the kernel to actually reap them. Returning only after
-/// the processes are gone guarantees that the next scan reports the app as
-/// no longer running, so the UI's "Quit" button can hide and the sidebar's
-/// running indicator clears in the same refresh cycle.
+/// Send SIGKILL to every process that matches the given app, then wait (up to
+/// `deadline`, see `Settings::kill_deadline_ms`) for the kernel to actually
+/// reap them. Returning only after the processes are gone guarantees that
+/// the next scan reports the app as no longer running, so the UI's "Quit"
+/// button can hide and the sidebar's running indicator clears in the same
+/// refresh cycle.
 ///
 /// Returns the number of processes the kernel accepted SIGKILL for.
 pub fn kill_app(
     bundle_path: Option<&Path>,
     bundle_id: Option<&str>,
     app_name: Option<&str>,
+    deadline: Duration,
 ) -> u32 {
     let mut sys = process_only_snapshot();
     let keys = MatchKeys::new(bundle_path, bundle_id, app_name, None);
@@ -228,7 +230,7 @@ pub fn kill_app(
     // through sysinfo only on the next refresh. We reuse `sys` and refresh
     // with `ProcessRefreshKind::nothing()` — we only need the live PID set,
     // not any per-process detail — so each iteration is cheap.
-    let deadline = std::time::Instant::now() + Duration::from_millis(2000);
+    let deadline = std::time::Instant::now() + deadline;
     loop {
         sleep(Duration::from_millis(50));
         sys.refresh_processes_specifics(
@@ -0,0 +1,233 @@
+//! Load, migrate and validate the persisted [`Settings`].
+//!
+//! The file carries a `schemaVersion`. [`migrate`] upgrades older documents
+//! one version at a time on the raw JSON, so a step can rename or reshape
+//! keys before serde sees them; keys a version doesn't know fall back to
+//! their defaults. Files from a newer build are rejected rather than
+//! silently downgraded.
+
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use super::{
+    paths::{expand_home, user_home},
+    source::SETAPP_DIR,
+};
+use crate::models::{BackupFormat, KillPolicy, RemovalMode, Settings};
+
+/// File name of the settings file inside the app config directory.
+pub const SETTINGS_FILE: &str = "settings.json";
+
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+pub const DEFAULT_KILL_DEADLINE_MS: u64 = 2000;
+pub const DEFAULT_RELATED_MAX_DEPTH: usize = 2;
+pub const MAX_KILL_DEADLINE_MS: u64 = 30_000;
+pub const MAX_RELATED_DEPTH: usize = 8;
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            schema_version: CURRENT_SCHEMA_VERSION,
+            scan_roots: vec![
+                "/Applications".into(),
+                SETAPP_DIR.into(),
+                "~/Applications".into(),
+            ],
+            removal_mode: RemovalMode::TrashOrDelete,
+            kill_policy: KillPolicy::Refuse,
+            kill_deadline_ms: DEFAULT_KILL_DEADLINE_MS,
+            related_max_depth: DEFAULT_RELATED_MAX_DEPTH,
+            transactional_uninstall: false,
+            backup_before_uninstall: false,
+            backups_dir: String::new(),
+            backup_format: BackupFormat::TarZst,
+        }
+    }
+}
+
+/// Absolute, or relative to the home folder.
+fn is_user_path(path: &str) -> bool {
+    path.starts_with('/') || path == "~" || path.starts_with("~/")
+}
+
+impl Settings {
+    /// `scan_roots` with `~` expanded to `home`.
+    pub fn scan_root_paths(&self, home: &Path) -> Vec<PathBuf> {
+        self.scan_roots
+            .iter()
+            .map(|r| expand_home(r, home))
+            .collect()
+    }
+
+    /// `backups_dir` with `~` expanded to `home`; `None` when unset, meaning
+    /// the app's own data folder.
+    pub fn backups_path(&self, home: &Path) -> Option<PathBuf> {
+        (!self.backups_dir.is_empty()).then(|| expand_home(&self.backups_dir, home))
+    }
+
+    /// `scan_roots` expanded against the current user's home.
+    pub fn user_scan_roots(&self) -> Vec<PathBuf> {
+        self.scan_root_paths(&user_home())
+    }
+
+    pub fn kill_deadline(&self) -> Duration {
+        Duration::from_millis(self.kill_deadline_ms)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.scan_roots.is_empty() {
+            bail!("At least one scan root is required");
+        }
+        for root in &self.scan_roots {
+            if !is_user_path(root) {
+                bail!("Scan root {root:?} must be absolute or start with ~/");
+            }
+        }
+        if !(self.backups_dir.is_empty() || is_user_path(&self.backups_dir)) {
+            bail!(
+                "Backups folder {:?} must be absolute or start with ~/",
+                self.backups_dir
+            );
+        }
+        if !(1..=MAX_RELATED_DEPTH).contains(&self.related_max_depth) {
+            bail!("Related file depth must be between 1 and {MAX_RELATED_DEPTH}");
+        }
+        if self.kill_deadline_ms > MAX_KILL_DEADLINE_MS {
+            bail!("Kill deadline must be at most {MAX_KILL_DEADLINE_MS} ms");
+        }
+        Ok(())
+    }
+}
+
+/// Upgrade a settings document to [`CURRENT_SCHEMA_VERSION`]. Documents
+/// without a version predate versioning and count as version 0.
+pub fn migrate(mut doc: Value) -> Result<Value> {
+    let Some(obj) = doc.as_object_mut() else {
+        bail!("Settings file is not a JSON object");
+    };
+    let mut version = obj
+        .get("schemaVersion")
+        .and_then(Value::as_u64)
+        .unwrap_or(0) as u32;
+    if version > CURRENT_SCHEMA_VERSION {
+        bail!(
+            "Settings were written by a newer version (schema {version}, this build understands {CURRENT_SCHEMA_VERSION})"
+        );
+    }
+    while version < CURRENT_SCHEMA_VERSION {
+        match version {
+            // 0 -> 1: same keys, now versioned.
+            0 => {}
+            _ => unreachable!("no migration from schema {version}"),
+        }
+        version += 1;
+        obj.insert("schemaVersion".into(), version.into());
+    }
+    Ok(doc)
+}
+
+/// Read, migrate and validate the settings file. A missing file yields the
+/// defaults.
+pub fn load_settings(path: &Path) -> Result<Settings> {
+    let raw = match fs::read(path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Settings::default()),
+        Err(e) => return Err(e).with_context(|| format!("Read {}", path.display())),
+    };
+    let doc: Value = serde_json::from_slice(&raw)
+        .with_context(|| format!("Invalid settings file {}", path.display()))?;
+    let settings: Settings = serde_json::from_value(migrate(doc)?)
+        .with_context(|| format!("Invalid settings file {}", path.display()))?;
+    settings.validate()?;
+    Ok(settings)
+}
+
+/// Validate and write `settings`, stamping the current schema version.
+pub fn save_settings(path: &Path, settings: &Settings) -> Result<Settings> {
+    settings.validate()?;
+    let settings = Settings {
+        schema_version: CURRENT_SCHEMA_VERSION,
+        ..settings.clone()
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Create {}", dir.display()))?;
+    }
+    fs::write(path, serde_json::to_vec_pretty(&settings)?)
+        .with_context(|| format!("Write {}", path.display()))?;
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_unversioned_document() {
+        let doc = serde_json::json!({ "killPolicy": "kill", "relatedMaxDepth": 3 });
+        let settings: Settings = serde_json::from_value(migrate(doc).unwrap()).unwrap();
+        assert_eq!(settings.schema_version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(settings.kill_policy, KillPolicy::Kill);
+        assert_eq!(settings.related_max_depth, 3);
+        assert_eq!(settings.kill_deadline_ms, DEFAULT_KILL_DEADLINE_MS);
+
+        let newer = serde_json::json!({ "schemaVersion": CURRENT_SCHEMA_VERSION + 1 });
+        assert!(migrate(newer).is_err());
+    }
+
+    #[test]
+    fn validates_and_round_trips() {
+        let dir = std::env::temp_dir().join(format!("settings-test-{}", std::process::id()));
+        let path = dir.join(SETTINGS_FILE);
+        let defaults = load_settings(&path).unwrap();
+        let custom = Settings {
+            schema_version: 0,
+            scan_roots: vec!["/Applications".into(), "~/Apps".into()],
+            removal_mode: RemovalMode::Trash,
+            ..Settings::default()
+        };
+        let saved = save_settings(&path, &custom).unwrap();
+        let loaded = load_settings(&path).unwrap();
+        let bad_depth = save_settings(
+            &path,
+            &Settings {
+                related_max_depth: 0,
+                ..Settings::default()
+            },
+        );
+        let bad_backups = save_settings(
+            &path,
+            &Settings {
+                backups_dir: "Backups".into(),
+                ..Settings::default()
+            },
+        );
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(defaults, Settings::default());
+        assert_eq!(saved.schema_version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(loaded, saved);
+        assert!(bad_depth.is_err() && bad_backups.is_err());
+        assert_eq!(
+            loaded.scan_root_paths(Path::new("/Users/a"))[1],
+            PathBuf::from("/Users/a/Apps")
+        );
+        let backups = Settings {
+            backups_dir: "~/Backups".into(),
+            ..Settings::default()
+        };
+        assert_eq!(
+            Settings::default().backups_path(Path::new("/Users/a")),
+            None
+        );
+        assert_eq!(
+            backups.backups_path(Path::new("/Users/a")),
+            Some(PathBuf::from("/Users/a/Backups"))
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! Infer how an app was installed.
+//!
+//! Each source leaves a cheap marker:
+//!
+//! - App Store: `Contents/_MASReceipt/receipt` inside the bundle,
+//! - Setapp: the bundle lives under `/Applications/Setapp`,
+//! - Homebrew: a Caskroom entry names the bundle (see `core::casks`),
+//! - installer packages: a receipt's bill of materials lists the bundle.
+//!
+//! Anything else with a readable `Info.plist` was most likely dragged into
+//! place.
+
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::models::InstallSource;
+
+pub const SETAPP_DIR: &str = "/Applications/Setapp";
+
+/// Where `installer` writes package receipts (`<id>.plist` + `<id>.bom`).
+pub const RECEIPT_DIRS: &[&str] = &["/private/var/db/receipts", "/Library/Receipts"];
+
+pub fn has_mas_receipt(app_path: &Path) -> bool {
+    app_path
+        .join("Contents")
+        .join("_MASReceipt")
+        .join("receipt")
+        .is_file()
+}
+
+pub fn is_setapp_path(app_path: &Path) -> bool {
+    app_path.starts_with(SETAPP_DIR)
+}
+
+/// `.app` names recorded in a bill of materials. BOM files store each path
+/// component as a NUL-terminated string, so bundle names can be picked out
+/// without decoding the tree.
+pub fn app_names_in_bom(bom: &[u8]) -> impl Iterator<Item = &str> {
+    bom.split(|b| *b == 0)
+        .filter(|s| s.len() > 4 && s.ends_with(b".app"))
+        .filter_map(|s| std::str::from_utf8(s).ok())
+        .map(|s| s.rsplit('/').next().unwrap_or(s))
+        .filter(|s| s.len() > 4)
+}
+
+/// Bundle names installed by third-party packages in `receipt_dirs`. Apple's
+/// own `com.apple.pkg.*` receipts are skipped: they are numerous, large, and
+/// never describe user-installed apps.
+pub fn pkg_installed_apps(receipt_dirs: &[PathBuf]) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for dir in receipt_dirs {
+        let Ok(rd) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in rd.flatten() {
+            let path = entry.path();
+            let Some(file) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if !file.ends_with(".bom") || file.starts_with("com.apple.") {
+                continue;
+            }
+            if let Ok(bom) = fs::read(&path) {
+                names.extend(app_names_in_bom(&bom).map(str::to_string));
+            }
+        }
+    }
+    names
+}
+
+/// Classify one bundle. `cask` is the token from `core::casks`, `pkg_apps`
+/// the result of [`pkg_installed_apps`].
+pub fn install_source(
+    app_path: &Path,
+    bundle_id: Option<&str>,
+    cask: Option<&str>,
+    pkg_apps: &HashSet<String>,
+) -> InstallSource {
+    if has_mas_receipt(app_path) {
+        InstallSource::AppStore
+    } else if is_setapp_path(app_path) {
+        InstallSource::Setapp
+    } else if cask.is_some() {
+        InstallSource::Homebrew
+    } else if app_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| pkg_apps.contains(n))
+    {
+        InstallSource::Pkg
+    } else if bundle_id.is_some() {
+        InstallSource::DragAndDrop
+    } else {
+        InstallSource::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_by_marker() {
+        let root = std::env::temp_dir().join(format!("source-test-{}", std::process::id()));
+        let mas = root.join("Pages.app");
+        fs::create_dir_all(mas.join("Contents/_MASReceipt")).unwrap();
+        fs::write(mas.join("Contents/_MASReceipt/receipt"), b"").unwrap();
+        let receipts = root.join("receipts");
+        fs::create_dir_all(&receipts).unwrap();
+        fs::write(
+            receipts.join("com.example.pkg.bom"),
+            b"BOMStore\0\0\x01Applications\0Example.app\0Contents\0",
+        )
+        .unwrap();
+        fs::write(receipts.join("com.apple.pkg.Core.bom"), b"\0Safari.app\0").unwrap();
+
+        let pkg_apps = pkg_installed_apps(&[receipts]);
+        let plain = |p: &str, bid: Option<&str>, cask: Option<&str>| {
+            install_source(&root.join(p), bid, cask, &pkg_apps)
+        };
+        let app_store = install_source(&mas, Some("com.apple.Pages"), None, &pkg_apps);
+        let pkg = plain("Example.app", Some("com.example"), None);
+        let brew = plain("Firefox.app", Some("org.mozilla.firefox"), Some("firefox"));
+        let dragged = plain("Safari.app", Some("com.apple.Safari"), None);
+        let unknown = plain("Broken.app", None, None);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(app_store, InstallSource::AppStore);
+        assert_eq!(pkg, InstallSource::Pkg);
+        assert_eq!(brew, InstallSource::Homebrew);
+        assert_eq!(dragged, InstallSource::DragAndDrop);
+        assert_eq!(unknown, InstallSource::Unknown);
+        assert_eq!(
+            install_source(
+                Path::new("/Applications/Setapp/CleanShot X.app"),
+                None,
+                None,
+                &HashSet::new()
+            ),
+            InstallSource::Setapp
+        );
+    }
+}
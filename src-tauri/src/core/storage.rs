@@ -0,0 +1,210 @@
+//! Disk usage of `~/Library`, broken down by app.
+//!
+//! Each top-level entry of the folders below is sized and attributed to at
+//! most one installed app, by bundle id first and exact name second. What
+//! nobody claims is reported as unattributed, which is where leftovers of
+//! already-removed apps show up.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    apps::compute_size,
+    paths::user_home,
+    progress::{ProgressKind, ProgressSink, Task},
+};
+use crate::models::{AppInfo, AppStorage, StorageEntry, StorageReport};
+
+/// `~/Library` folders whose entries are attributed.
+pub const STORAGE_FOLDERS: &[&str] = &[
+    "Application Support",
+    "Caches",
+    "Containers",
+    "Group Containers",
+    "Logs",
+];
+
+/// Bundle id matches outrank name matches whatever their length.
+const BUNDLE_ID_RANK: usize = 1 << 16;
+
+/// How strongly `app` claims an entry named `name` (lowercased), if at all.
+/// A bundle id must match whole dot-separated segments, so `com.foo` does
+/// not claim `com.foobar`; a longer bundle id beats a shorter one.
+fn claim(name: &str, app: &AppInfo) -> Option<usize> {
+    if let Some(bid) = &app.bundle_id {
+        let bid = bid.to_lowercase();
+        if name == bid
+            || name.starts_with(&format!("{bid}."))
+            || name.ends_with(&format!(".{bid}"))
+            || name.contains(&format!(".{bid}."))
+        {
+            return Some(BUNDLE_ID_RANK + bid.len());
+        }
+    }
+    (name == app.name.to_lowercase()).then_some(app.name.len())
+}
+
+/// Index into `apps` of the app that claims `name`.
+fn attribute(name: &str, apps: &[AppInfo]) -> Option<usize> {
+    let name = name.to_lowercase();
+    apps.iter()
+        .enumerate()
+        .filter_map(|(i, a)| claim(&name, a).map(|rank| (rank, i)))
+        .max_by_key(|&(rank, i)| (rank, std::cmp::Reverse(i)))
+        .map(|(_, i)| i)
+}
+
+fn by_size_desc(entries: &mut [StorageEntry]) {
+    entries.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+}
+
+/// Size every top-level entry of the [`STORAGE_FOLDERS`] under `home` and
+/// attribute it to one of `apps`. Reported to `sink` as a
+/// `storage_analysis` task, one step per entry.
+pub fn analyze_library(home: &Path, apps: &[AppInfo], sink: &dyn ProgressSink) -> StorageReport {
+    let task = Task::start(
+        sink,
+        ProgressKind::StorageAnalysis,
+        "Listing Library folders...",
+    );
+    let library = home.join("Library");
+    let mut candidates: Vec<(PathBuf, &str)> = Vec::new();
+    for folder in STORAGE_FOLDERS {
+        // Unreadable folders (no Full Disk Access) are simply skipped; see
+        // `core::access`.
+        let Ok(entries) = fs::read_dir(library.join(folder)) else {
+            continue;
+        };
+        let mut paths: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+        paths.sort();
+        candidates.extend(paths.into_iter().map(|p| (p, *folder)));
+    }
+
+    let total = candidates.len();
+    let mut per_app: HashMap<usize, Vec<StorageEntry>> = HashMap::new();
+    let mut unattributed = Vec::new();
+    let mut total_bytes = 0u64;
+    for (i, (path, category)) in candidates.into_iter().enumerate() {
+        task.set_items(i as u64, Some(total as u64));
+        task.set_bytes(total_bytes);
+        task.progress(
+            i as f32 / total.max(1) as f32,
+            format!("Sizing {}...", path.display()),
+        );
+        let bytes = compute_size(&path).unwrap_or(0);
+        total_bytes += bytes;
+        let owner = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| attribute(n, apps));
+        let entry = StorageEntry {
+            path,
+            category: category.to_string(),
+            bytes,
+        };
+        match owner {
+            Some(app) => per_app.entry(app).or_default().push(entry),
+            None => unattributed.push(entry),
+        }
+    }
+
+    let mut ranked: Vec<AppStorage> = per_app
+        .into_iter()
+        .map(|(i, mut entries)| {
+            by_size_desc(&mut entries);
+            let app = &apps[i];
+            AppStorage {
+                app_path: app.path.clone(),
+                name: app.name.clone(),
+                bundle_id: app.bundle_id.clone(),
+                bytes: entries.iter().map(|e| e.bytes).sum(),
+                entries,
+            }
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+    by_size_desc(&mut unattributed);
+
+    task.set_items(total as u64, Some(total as u64));
+    task.set_bytes(total_bytes);
+    task.finish(format!(
+        "{} app(s) hold data outside their bundle.",
+        ranked.len()
+    ));
+    StorageReport {
+        total_bytes,
+        apps: ranked,
+        unattributed,
+    }
+}
+
+/// [`analyze_library`] for the current user.
+pub fn analyze_storage(apps: &[AppInfo], sink: &dyn ProgressSink) -> StorageReport {
+    let home = user_home();
+    analyze_library(&home, apps, sink)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::progress::NullSink;
+
+    fn app(name: &str, bid: Option<&str>) -> AppInfo {
+        AppInfo {
+            path: PathBuf::from(format!("/Applications/{name}.app")),
+            name: name.to_string(),
+            bundle_id: bid.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn attributes_by_bundle_id_segments_then_name() {
+        let apps = [
+            app("Foo", Some("com.foo")),
+            app("Foo Helper", Some("com.foo.helper")),
+            app("Bar", None),
+        ];
+        assert_eq!(attribute("com.foo", &apps), Some(0));
+        assert_eq!(attribute("com.foo.savedState", &apps), Some(0));
+        assert_eq!(attribute("com.foo.helper", &apps), Some(1));
+        assert_eq!(attribute("ABCDE12345.com.foo", &apps), Some(0));
+        assert_eq!(attribute("com.foobar", &apps), None);
+        assert_eq!(attribute("bar", &apps), Some(2));
+        assert_eq!(attribute("Barista", &apps), None);
+    }
+
+    #[test]
+    fn ranks_apps_and_lists_unattributed() {
+        let home = std::env::temp_dir().join(format!("storage-test-{}", std::process::id()));
+        let lib = home.join("Library");
+        let write = |rel: &str, len: usize| {
+            let p = lib.join(rel);
+            fs::create_dir_all(p.parent().unwrap()).unwrap();
+            fs::write(p, vec![0u8; len]).unwrap();
+        };
+        write("Caches/com.foo/cache.db", 100);
+        write("Application Support/Foo/data", 50);
+        write("Containers/com.bar/Data/big", 400);
+        write("Logs/Gone/old.log", 30);
+        write("Preferences/com.foo.plist", 1000);
+
+        let apps = [app("Foo", Some("com.foo")), app("Bar", Some("com.bar"))];
+        let report = analyze_library(&home, &apps, &NullSink);
+        fs::remove_dir_all(&home).unwrap();
+
+        assert_eq!(report.total_bytes, 580);
+        let ranked: Vec<(&str, u64, usize)> = report
+            .apps
+            .iter()
+            .map(|a| (a.name.as_str(), a.bytes, a.entries.len()))
+            .collect();
+        assert_eq!(ranked, vec![("Bar", 400, 1), ("Foo", 150, 2)]);
+        assert_eq!(report.apps[1].entries[0].category, "Caches");
+        assert_eq!(report.unattributed.len(), 1);
+        assert_eq!(report.unattributed[0].path, lib.join("Logs").join("Gone"));
+    }
+}
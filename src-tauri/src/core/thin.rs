@@ -0,0 +1,353 @@
+//! Strip unused architectures from universal (fat) binaries inside a bundle.
+//!
+//! Every fat Mach-O under `Contents/` that contains the requested
+//! architecture is rewritten as a thin Mach-O holding only that slice. The
+//! original is moved to the Trash first, so a bad thin can be undone by
+//! putting it back. Files without the requested slice are left alone.
+//!
+//! Thinning changes the bytes of nested code, which breaks the bundle seal
+//! recorded in `_CodeSignature/CodeResources`. Signed bundles are refused
+//! unless the caller explicitly accepts that the signature will no longer
+//! validate.
+
+use anyhow::{Context, Result, anyhow, bail};
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+use super::{
+    macho::{self, Slice},
+    plist_info::read_info_from_app,
+    protection::app_protection,
+    running::is_app_running_simple,
+};
+use crate::models::{
+    AppProtection, Architecture, FailureKind, ThinReport, ThinnedFile, UninstallFailure,
+};
+
+/// True if the bundle carries a code signature: either a sealed resources
+/// file or an embedded signature in the main executable.
+pub fn is_signed_bundle(app_path: &Path, executable: Option<&str>) -> bool {
+    if app_path
+        .join("Contents")
+        .join("_CodeSignature")
+        .join("CodeResources")
+        .exists()
+    {
+        return true;
+    }
+    executable
+        .map(|exe| app_path.join("Contents").join("MacOS").join(exe))
+        .and_then(|p| macho::read_code_signature(&p).ok().flatten())
+        .is_some()
+}
+
+/// The slice to keep from a fat file, or `None` if the file is thin, isn't
+/// a Mach-O, or doesn't contain `arch`.
+fn slice_to_keep(path: &Path, arch: Architecture) -> Option<Slice> {
+    let slices = macho::read_slices(path).ok()?;
+    let is_fat = slices.first().is_some_and(|s| s.offset != 0);
+    if !is_fat || slices.len() < 2 {
+        return None;
+    }
+    slices.into_iter().find(|s| s.architecture() == arch)
+}
+
+/// Write the `arch` slice of the fat file `src` to `dst` as a thin Mach-O.
+/// Returns `(original_size, new_size)`, or `None` when `src` has nothing to
+/// strip.
+pub fn thin_file(src: &Path, dst: &Path, arch: Architecture) -> Result<Option<(u64, u64)>> {
+    let Some(slice) = slice_to_keep(src, arch) else {
+        return Ok(None);
+    };
+    let mut input = File::open(src).with_context(|| format!("Open {}", src.display()))?;
+    let original_size = input.metadata()?.len();
+    if slice.offset.saturating_add(slice.size) > original_size {
+        bail!("Slice extends past end of {}", src.display());
+    }
+    input.seek(SeekFrom::Start(slice.offset))?;
+    let mut output = File::create(dst).with_context(|| format!("Create {}", dst.display()))?;
+    let copied = io::copy(&mut input.take(slice.size), &mut output)?;
+    if copied != slice.size {
+        bail!("Short read while thinning {}", src.display());
+    }
+    Ok(Some((original_size, copied)))
+}
+
+/// Thin one file in place: write the slice next to it, back up the original
+/// with `move_to_trash`, then move the thin copy into its place.
+fn thin_in_place(
+    path: &Path,
+    arch: Architecture,
+    move_to_trash: &dyn Fn(&Path) -> Result<()>,
+) -> Result<Option<ThinnedFile>> {
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("No file name: {}", path.display()))?;
+    let tmp = path.with_file_name(format!(".{}.thin", file_name.to_string_lossy()));
+
+    let sizes = match thin_file(path, &tmp, arch) {
+        Ok(Some(sizes)) => sizes,
+        Ok(None) => return Ok(None),
+        Err(e) => {
+            let _ = fs::remove_file(&tmp);
+            return Err(e);
+        }
+    };
+    let perms = fs::metadata(path)?.permissions();
+    fs::set_permissions(&tmp, perms)?;
+
+    // Back up the original. No permanent-delete fallback here: if the Trash
+    // is unavailable we would lose the only copy of the stripped slices.
+    if let Err(e) = move_to_trash(path) {
+        let _ = fs::remove_file(&tmp);
+        bail!("Failed to move {} to Trash: {e}", path.display());
+    }
+    // The original is in the Trash now; keep the thin copy rather than leave
+    // nothing behind, and say where it is.
+    fs::rename(&tmp, path).with_context(|| {
+        format!(
+            "Replace {} with thin copy; the original is in the Trash and the thin copy is at {}",
+            path.display(),
+            tmp.display()
+        )
+    })?;
+
+    Ok(Some(ThinnedFile {
+        path: path.to_path_buf(),
+        original_size: sizes.0,
+        new_size: sizes.1,
+    }))
+}
+
+/// Rewrite every fat Mach-O under `<app>/Contents` to keep only `arch`.
+/// Refuses Apple apps, running apps, and signed bundles unless
+/// `accept_invalid_signature` is set.
+pub fn thin_app(
+    app_path: &Path,
+    arch: Architecture,
+    accept_invalid_signature: bool,
+) -> Result<ThinReport> {
+    thin_app_with(
+        app_path,
+        arch,
+        accept_invalid_signature,
+        &|bundle_id| is_app_running_simple(Some(app_path), bundle_id, None),
+        &|path| trash::delete(path).map_err(|e| anyhow!("{e}")),
+    )
+}
+
+/// [`thin_app`] with the running check (given the bundle id) and the Trash
+/// supplied by the caller.
+fn thin_app_with(
+    app_path: &Path,
+    arch: Architecture,
+    accept_invalid_signature: bool,
+    is_running: &dyn Fn(Option<&str>) -> bool,
+    move_to_trash: &dyn Fn(&Path) -> Result<()>,
+) -> Result<ThinReport> {
+    let info = read_info_from_app(app_path)?;
+    let executable = info.executable.as_deref();
+    if app_protection(app_path, info.bundle_id.as_deref(), executable) != AppProtection::None {
+        bail!(
+            "{} is an Apple app and cannot be thinned",
+            app_path.display()
+        );
+    }
+    if is_running(info.bundle_id.as_deref()) {
+        bail!("{} is running. Quit it before thinning", app_path.display());
+    }
+    if !accept_invalid_signature && is_signed_bundle(app_path, executable) {
+        bail!(
+            "{} is code-signed; thinning will invalidate its signature",
+            app_path.display()
+        );
+    }
+
+    let candidates: Vec<PathBuf> = WalkDir::new(app_path.join("Contents"))
+        .follow_links(false)
+        .into_iter()
+        .flatten()
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.into_path())
+        .collect();
+
+    let mut report = ThinReport {
+        app_path: app_path.to_path_buf(),
+        architecture: arch,
+        thinned: Vec::new(),
+        failed: Vec::new(),
+        bytes_saved: 0,
+    };
+    for path in candidates {
+        match thin_in_place(&path, arch, move_to_trash) {
+            Ok(Some(f)) => {
+                report.bytes_saved += f.original_size.saturating_sub(f.new_size);
+                report.thinned.push(f);
+            }
+            Ok(None) => {}
+            Err(e) => report.failed.push(UninstallFailure {
+                path,
+                error: format!("{e:?}"),
+                kind: FailureKind::Error,
+            }),
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fat_fixture(path: &Path) {
+        // Two slices: x86_64 at 0x1000 ("X" * 16), arm64 at 0x2000 ("A" * 32).
+        let mut b = Vec::new();
+        for v in [0xcafe_babeu32, 2] {
+            b.extend(v.to_be_bytes());
+        }
+        for v in [0x0100_0007u32, 3, 0x1000, 16, 12] {
+            b.extend(v.to_be_bytes());
+        }
+        for v in [0x0100_000cu32, 0, 0x2000, 32, 14] {
+            b.extend(v.to_be_bytes());
+        }
+        b.resize(0x1000, 0);
+        b.extend([b'X'; 16]);
+        b.resize(0x2000, 0);
+        b.extend([b'A'; 32]);
+        fs::write(path, b).unwrap();
+    }
+
+    #[test]
+    fn thin_file_extracts_requested_slice() {
+        let dir = std::env::temp_dir().join(format!("thin-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let src = dir.join("fat");
+        let dst = dir.join("thin");
+        fat_fixture(&src);
+
+        let sizes = thin_file(&src, &dst, Architecture::Arm64).unwrap();
+        let thin = fs::read(&dst).unwrap();
+        let missing = thin_file(&src, &dir.join("none"), Architecture::I386).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(sizes, Some((0x2000 + 32, 32)));
+        assert_eq!(thin, vec![b'A'; 32]);
+        assert_eq!(missing, None);
+    }
+
+    fn fixture_bundle(root: &Path, bundle_id: &str) -> PathBuf {
+        let app = root.join("Foo.app");
+        let contents = app.join("Contents");
+        fs::create_dir_all(contents.join("MacOS")).unwrap();
+        fs::create_dir_all(contents.join("Frameworks")).unwrap();
+        fs::write(
+            contents.join("Info.plist"),
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0"><dict>
+<key>CFBundleIdentifier</key><string>{bundle_id}</string>
+<key>CFBundleExecutable</key><string>Foo</string>
+</dict></plist>"#
+            ),
+        )
+        .unwrap();
+        fat_fixture(&contents.join("MacOS").join("Foo"));
+        fat_fixture(&contents.join("Frameworks").join("libfoo.dylib"));
+        fs::write(contents.join("Frameworks").join("README"), "text").unwrap();
+        app
+    }
+
+    #[test]
+    fn thin_app_thins_every_fat_file_in_the_bundle() {
+        let root = std::env::temp_dir().join(format!("thin-app-test-{}", std::process::id()));
+        let app = fixture_bundle(&root, "com.foo");
+        let trashed = std::cell::RefCell::new(Vec::new());
+        let trash = |p: &Path| {
+            trashed.borrow_mut().push(p.to_path_buf());
+            Ok(fs::remove_file(p)?)
+        };
+
+        let report = thin_app_with(&app, Architecture::Arm64, false, &|_| false, &trash).unwrap();
+        let exe = fs::read(app.join("Contents/MacOS/Foo")).unwrap();
+        let readme = fs::read_to_string(app.join("Contents/Frameworks/README")).unwrap();
+        let leftovers = fs::read_dir(app.join("Contents/MacOS")).unwrap().count();
+        fs::remove_dir_all(&root).unwrap();
+
+        let mut thinned: Vec<_> = report.thinned.iter().map(|f| f.path.clone()).collect();
+        thinned.sort();
+        let expected = vec![
+            app.join("Contents/Frameworks/libfoo.dylib"),
+            app.join("Contents/MacOS/Foo"),
+        ];
+        assert_eq!(thinned, expected);
+        let mut trashed = trashed.into_inner();
+        trashed.sort();
+        assert_eq!(trashed, expected);
+        assert!(report.failed.is_empty());
+        assert_eq!(report.bytes_saved, 2 * 0x2000);
+        assert_eq!(exe, vec![b'A'; 32]);
+        assert_eq!(readme, "text");
+        assert_eq!(leftovers, 1, "no temporary copies left");
+    }
+
+    #[test]
+    fn thin_app_refuses_protected_running_and_signed_apps() {
+        let root = std::env::temp_dir().join(format!("thin-refuse-test-{}", std::process::id()));
+        let no_trash = |_: &Path| -> Result<()> { panic!("nothing may be trashed") };
+        let thin = |app: &Path, accept: bool, running: bool| {
+            thin_app_with(app, Architecture::Arm64, accept, &|_| running, &no_trash)
+                .map(|r| r.thinned.len())
+                .map_err(|e| e.to_string())
+        };
+
+        let apple = fixture_bundle(&root.join("apple"), "com.apple.foo");
+        // Without an executable to check the signature of, an Apple id is
+        // trusted as is.
+        fs::write(
+            apple.join("Contents/Info.plist"),
+            r#"<plist version="1.0"><dict>
+<key>CFBundleIdentifier</key><string>com.apple.foo</string>
+</dict></plist>"#,
+        )
+        .unwrap();
+        let protected = thin(&apple, true, false);
+        let app = fixture_bundle(&root.join("foo"), "com.foo");
+        let running = thin(&app, true, true);
+        fs::create_dir_all(app.join("Contents/_CodeSignature")).unwrap();
+        fs::write(app.join("Contents/_CodeSignature/CodeResources"), "").unwrap();
+        let signed = thin(&app, false, false);
+        let exe = fs::read(app.join("Contents/MacOS/Foo")).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(protected.unwrap_err().contains("Apple app"));
+        assert!(running.unwrap_err().contains("is running"));
+        assert!(signed.unwrap_err().contains("code-signed"));
+        assert_eq!(exe.len(), 0x2000 + 32, "left fat");
+    }
+
+    #[test]
+    fn failed_replace_keeps_the_thin_copy_and_names_it() {
+        let dir = std::env::temp_dir().join(format!("thin-replace-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("fat");
+        fat_fixture(&path);
+        // Something takes the name while the original is in the Trash.
+        let trash = |p: &Path| {
+            fs::remove_file(p)?;
+            Ok(fs::create_dir_all(p.join("taken"))?)
+        };
+
+        let err = thin_in_place(&path, Architecture::Arm64, &trash).unwrap_err();
+        let tmp = dir.join(".fat.thin");
+        let kept = fs::read(&tmp).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(format!("{err:#}").contains(&tmp.display().to_string()));
+        assert_eq!(kept, vec![b'A'; 32]);
+    }
+}
@@ -0,0 +1,371 @@
+//! All-or-nothing removal for transactional uninstalls.
+//!
+//! Every path is pre-flighted (exists, allowed by the [`RemovalPolicy`],
+//! parent folder writable) before anything moves. Items are then *staged*:
+//! renamed into a hidden folder next to them. A rename within one folder
+//! stays on the same volume, so it is atomic and undone by renaming back.
+//! If staging fails part way, [`Transaction::rollback`] restores what was
+//! already staged.
+//!
+//! [`Transaction::commit`] then returns each staged item to its location
+//! and trashes it with `trash::delete`, so Finder's Put Back works. If the
+//! Trash refuses an item, the items already trashed are put back and the
+//! rest unstaged. Items are never deleted in place of trashing; only
+//! `RemovalMode::Delete` deletes, straight from the staging folder.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::{access::probe_writable, error::CoreError, trash::RemovalPolicy};
+use crate::models::RemovalMode;
+
+fn parent_of(path: &Path) -> Result<&Path, CoreError> {
+    path.parent().ok_or_else(|| CoreError::Protected {
+        path: Some(path.into()),
+        reason: format!("{} has no parent folder", path.display()),
+    })
+}
+
+/// Check that `path` can be staged: it exists, the removal policy allows
+/// it, and its parent folder accepts new entries.
+pub fn preflight(path: &Path) -> Result<(), CoreError> {
+    fs::symlink_metadata(path).map_err(|e| CoreError::io(path, e))?;
+    RemovalPolicy::for_current_user()
+        .check(path)
+        .map_err(|r| CoreError::refused(path, r))?;
+
+    let parent = parent_of(path)?;
+    probe_writable(parent).map_err(|e| CoreError::io(parent, e))
+}
+
+struct Staged {
+    original: PathBuf,
+    /// Hidden sibling folder holding the item under its own name.
+    holder: PathBuf,
+}
+
+impl Staged {
+    fn staged_path(&self) -> PathBuf {
+        self.holder
+            .join(self.original.file_name().unwrap_or_default())
+    }
+
+    fn restore(&self) -> Result<(), CoreError> {
+        fs::rename(self.staged_path(), &self.original)
+            .map_err(|e| CoreError::io(&self.original, e))?;
+        let _ = fs::remove_dir(&self.holder);
+        Ok(())
+    }
+}
+
+/// Items staged for removal, in staging order.
+#[derive(Default)]
+pub struct Transaction {
+    staged: Vec<Staged>,
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.staged.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.staged.is_empty()
+    }
+
+    /// Rename `path` into a hidden folder next to it.
+    pub fn stage(&mut self, path: &Path) -> Result<(), CoreError> {
+        let name = path.file_name().ok_or_else(|| CoreError::Protected {
+            path: Some(path.into()),
+            reason: format!("{} has no file name", path.display()),
+        })?;
+        let holder = parent_of(path)?.join(format!(
+            ".{}.uninstall-{}",
+            name.to_string_lossy(),
+            std::process::id()
+        ));
+        fs::create_dir(&holder).map_err(|e| CoreError::io(&holder, e))?;
+        if let Err(e) = fs::rename(path, holder.join(name)) {
+            let _ = fs::remove_dir(&holder);
+            return Err(CoreError::io(path, e));
+        }
+        self.staged.push(Staged {
+            original: path.to_path_buf(),
+            holder,
+        });
+        Ok(())
+    }
+
+    /// Put every staged item back, newest first. Returns the items that
+    /// could not be restored.
+    pub fn rollback(self) -> Vec<(PathBuf, CoreError)> {
+        self.staged
+            .into_iter()
+            .rev()
+            .filter_map(|s| s.restore().err().map(|e| (s.original, e)))
+            .collect()
+    }
+
+    /// Move every staged item to the Trash with `trash::delete`, or delete
+    /// it with `RemovalMode::Delete`. Each item goes back to its original
+    /// location just before it is trashed, so Finder's Put Back knows
+    /// where it came from. `TrashOrDelete` is as strict as `Trash` here:
+    /// falling back to deletion would make a failed commit unrecoverable.
+    ///
+    /// If an item can't be trashed, the items already in the Trash are put
+    /// back, the rest are unstaged, and the failure is returned. Otherwise
+    /// `on_item` is called once per item, in staging order; an error there
+    /// means the item is gone from its location but could not be deleted.
+    pub fn commit(
+        self,
+        mode: RemovalMode,
+        on_item: impl FnMut(PathBuf, Result<(), CoreError>),
+    ) -> Result<(), CommitFailure> {
+        self.commit_with(
+            mode,
+            |p| trash::delete(p).map_err(|e| e.to_string()),
+            put_back,
+            on_item,
+        )
+    }
+
+    /// [`Transaction::commit`] with the Trash supplied by the caller:
+    /// `move_to_trash` trashes the item at a path, `put_back` restores the
+    /// item trashed from a path.
+    pub fn commit_with(
+        self,
+        mode: RemovalMode,
+        move_to_trash: impl Fn(&Path) -> Result<(), String>,
+        put_back: impl Fn(&Path) -> Result<(), String>,
+        mut on_item: impl FnMut(PathBuf, Result<(), CoreError>),
+    ) -> Result<(), CommitFailure> {
+        if mode == RemovalMode::Delete {
+            for s in self.staged {
+                let staged = s.staged_path();
+                let result = remove_all(&staged).map_err(|e| CoreError::io(&staged, e));
+                if result.is_ok() {
+                    let _ = fs::remove_dir(&s.holder);
+                }
+                on_item(s.original, result);
+            }
+            return Ok(());
+        }
+
+        for (trashed, s) in self.staged.iter().enumerate() {
+            let result = s.restore().and_then(|()| {
+                move_to_trash(&s.original).map_err(|message| CoreError::TrashUnavailable {
+                    path: s.original.clone(),
+                    message,
+                })
+            });
+            if let Err(error) = result {
+                return Err(CommitFailure {
+                    path: s.original.clone(),
+                    error,
+                    unrestored: self.undo_commit(trashed, put_back),
+                });
+            }
+        }
+        for s in self.staged {
+            on_item(s.original, Ok(()));
+        }
+        Ok(())
+    }
+
+    /// Put back the first `trashed` items from the Trash and unstage the
+    /// rest, newest first. Returns the items that could not be restored.
+    fn undo_commit(
+        self,
+        trashed: usize,
+        put_back: impl Fn(&Path) -> Result<(), String>,
+    ) -> Vec<(PathBuf, CoreError)> {
+        let mut unrestored = Vec::new();
+        for (i, s) in self.staged.iter().enumerate().rev() {
+            let result = if i < trashed {
+                put_back(&s.original).map_err(|message| CoreError::Io {
+                    path: Some(s.original.clone()),
+                    message,
+                })
+            } else if fs::symlink_metadata(s.staged_path()).is_ok() {
+                s.restore()
+            } else {
+                // Unstaged already; the Trash refused it.
+                Ok(())
+            };
+            if let Err(e) = result {
+                log::error!("Could not restore {}: {e}", s.original.display());
+                unrestored.push((s.original.clone(), e));
+            }
+        }
+        unrestored
+    }
+}
+
+/// Why [`Transaction::commit`] failed, after putting everything back it
+/// could.
+#[derive(Debug)]
+pub struct CommitFailure {
+    pub path: PathBuf,
+    pub error: CoreError,
+    pub unrestored: Vec<(PathBuf, CoreError)>,
+}
+
+/// Put the item most recently trashed from `original` back.
+#[cfg(not(target_os = "macos"))]
+fn put_back(original: &Path) -> Result<(), String> {
+    use trash::os_limited::{list, restore_all};
+
+    let item = list()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|i| i.original_path() == original)
+        .max_by_key(|i| i.time_deleted)
+        .ok_or_else(|| format!("{} is not in the Trash", original.display()))?;
+    restore_all([item]).map_err(|e| e.to_string())
+}
+
+/// The Trash can't be listed on macOS; Finder's Put Back can still restore
+/// the item.
+#[cfg(target_os = "macos")]
+fn put_back(original: &Path) -> Result<(), String> {
+    Err(format!(
+        "{} is in the Trash; restore it with Put Back in Finder",
+        original.display()
+    ))
+}
+
+fn remove_all(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("{name}-{}", std::process::id()));
+        fs::create_dir_all(root.join("a.app")).unwrap();
+        fs::write(root.join("a.app").join("Info.plist"), "x").unwrap();
+        fs::write(root.join("b.plist"), "y").unwrap();
+        root
+    }
+
+    #[test]
+    fn rollback_restores_staged_items() {
+        let root = scratch("txn-rollback");
+        let (a, b) = (root.join("a.app"), root.join("b.plist"));
+
+        let mut tx = Transaction::new();
+        tx.stage(&a).unwrap();
+        let staged_away = !a.exists();
+        let missing = tx.stage(&root.join("missing"));
+        tx.stage(&b).unwrap();
+        let unrestored = tx.rollback();
+        let contents = fs::read_to_string(a.join("Info.plist")).ok();
+        let leftovers: Vec<_> = fs::read_dir(&root)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(staged_away);
+        assert!(matches!(missing, Err(CoreError::NotFound { .. })));
+        assert!(unrestored.is_empty());
+        assert_eq!(contents.as_deref(), Some("x"));
+        assert_eq!(leftovers.len(), 2, "holders removed: {leftovers:?}");
+    }
+
+    #[test]
+    fn commit_removes_from_original_location() {
+        let root = scratch("txn-commit");
+        let (a, b) = (root.join("a.app"), root.join("b.plist"));
+
+        let preflight_missing = preflight(&root.join("missing"));
+        let preflight_ok = preflight(&a);
+        let mut tx = Transaction::new();
+        tx.stage(&a).unwrap();
+        tx.stage(&b).unwrap();
+        let mut committed = Vec::new();
+        tx.commit(RemovalMode::Delete, |p, r| committed.push((p, r.is_ok())))
+            .unwrap();
+        let empty = fs::read_dir(&root).unwrap().next().is_none();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(matches!(preflight_missing, Err(CoreError::NotFound { .. })));
+        assert!(preflight_ok.is_ok());
+        assert_eq!(committed, vec![(a, true), (b, true)]);
+        assert!(empty);
+    }
+
+    #[test]
+    fn failed_commit_puts_trashed_items_back() {
+        let root = scratch("txn-commit-fail");
+        let (a, b) = (root.join("a.app"), root.join("b.plist"));
+        let trash = root.join("Trash");
+        fs::create_dir_all(&trash).unwrap();
+        let trashed_from = std::cell::RefCell::new(Vec::new());
+        let move_to_trash = |p: &Path| {
+            if p.ends_with("b.plist") {
+                return Err("no Trash on this volume".to_string());
+            }
+            trashed_from.borrow_mut().push(p.to_path_buf());
+            let name = p.file_name().unwrap();
+            fs::rename(p, trash.join(name)).map_err(|e| e.to_string())
+        };
+        let put_back = |p: &Path| {
+            let name = p.file_name().unwrap();
+            fs::rename(trash.join(name), p).map_err(|e| e.to_string())
+        };
+
+        let mut tx = Transaction::new();
+        tx.stage(&a).unwrap();
+        tx.stage(&b).unwrap();
+        let failed = tx
+            .commit_with(
+                RemovalMode::TrashOrDelete,
+                move_to_trash,
+                put_back,
+                |_, _| panic!("no items on failure"),
+            )
+            .unwrap_err();
+        let restored = fs::read_to_string(a.join("Info.plist")).ok();
+        let b_restored = fs::read_to_string(&b).ok();
+        let trash_empty = fs::read_dir(&trash).unwrap().next().is_none();
+        let leftovers = fs::read_dir(&root).unwrap().count();
+
+        // With every item accepted, each is trashed from where it was.
+        fs::remove_file(&b).unwrap();
+        let mut tx = Transaction::new();
+        tx.stage(&a).unwrap();
+        let mut committed = Vec::new();
+        tx.commit_with(RemovalMode::Trash, move_to_trash, put_back, |p, r| {
+            committed.push((p, r.is_ok()))
+        })
+        .unwrap();
+        let in_trash = fs::read_to_string(trash.join("a.app").join("Info.plist")).ok();
+        let gone = !a.exists();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(failed.path, b);
+        assert!(matches!(failed.error, CoreError::TrashUnavailable { .. }));
+        assert!(failed.unrestored.is_empty());
+        assert_eq!(restored.as_deref(), Some("x"));
+        assert_eq!(b_restored.as_deref(), Some("y"), "never deleted");
+        assert!(trash_empty);
+        assert_eq!(leftovers, 3, "holders removed");
+        assert_eq!(committed, vec![(a.clone(), true)]);
+        assert_eq!(trashed_from.into_inner(), vec![a.clone(), a]);
+        assert_eq!(in_trash.as_deref(), Some("x"));
+        assert!(gone);
+    }
+}
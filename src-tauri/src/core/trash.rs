@@ -1,35 +1,229 @@
-//! Trash-or-remove helpers and "is this path system-protected?" classifier.
-
-use anyhow::{Context, Result};
-use std::{fs, path::Path};
-
-pub fn move_to_trash_or_remove(path: &Path) -> Result<()> {
-    match trash::delete(path) {
-        Ok(_) => Ok(()),
-        Err(_trash_err) => {
-            if path.is_dir() {
-                fs::remove_dir_all(path)
-                    .with_context(|| format!("Failed to remove dir {}", path.display()))?;
-            } else if path.is_file() {
-                fs::remove_file(path)
-                    .with_context(|| format!("Failed to remove file {}", path.display()))?;
-            } else {
-                return Err(anyhow::anyhow!("Unknown path type: {}", path.display()));
+//! Trash-or-remove helpers, the removal safety policy, and the "is this
+//! path system-protected?" classifier.
+//!
+//! Every removal goes through [`RemovalPolicy::check`] first. The frontend
+//! sends whatever paths the user ticked, and `find_related_paths` matches by
+//! substring, so without the policy a stray `~/Library/Preferences` or a
+//! vendor folder shared by several apps would be deleted wholesale.
+
+use home::home_dir;
+use std::{
+    fmt, fs,
+    path::{Component, Path, PathBuf},
+};
+
+use super::error::CoreError;
+use crate::models::RemovalMode;
+
+/// Why [`RemovalPolicy::check`] refused a path. Removal functions surface it
+/// as [`CoreError::Protected`] so callers report a refusal rather than an
+/// I/O failure.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Refusal {
+    /// Relative paths or paths containing `..` can't be reasoned about.
+    NotAbsolute,
+    /// `/`, the home directory, `/Users`, `/Applications` and friends.
+    Root,
+    /// `~/Library`, `/Library`, or a direct child such as
+    /// `~/Library/Preferences`.
+    LibraryRoot,
+    /// Anything under `/System`, `/bin`, `/sbin` or `/usr` (except
+    /// `/usr/local`).
+    SystemLocation,
+    /// A path component named after an Apple bundle id (`com.apple.*`).
+    AppleBundle,
+    /// A vendor folder shared by several apps, e.g.
+    /// `~/Library/Application Support/Google`.
+    SharedVendorFolder,
+    /// A path inside the home directory that resolves (through a symlink)
+    /// to somewhere outside it.
+    EscapesHome,
+}
+
+impl fmt::Display for Refusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Refusal::NotAbsolute => "path is not absolute and normalized",
+            Refusal::Root => "path is a top-level system or user folder",
+            Refusal::LibraryRoot => "path is a Library folder shared by every app",
+            Refusal::SystemLocation => "path is inside a system location",
+            Refusal::AppleBundle => "path belongs to an Apple component (com.apple.*)",
+            Refusal::SharedVendorFolder => "path is a vendor folder shared by several apps",
+            Refusal::EscapesHome => "path is a symlink that leads outside the home directory",
+        };
+        f.write_str(s)
+    }
+}
+
+impl std::error::Error for Refusal {}
+
+/// Exact paths that are never removed.
+const DENIED_ROOTS: &[&str] = &[
+    "/",
+    "/Applications",
+    "/Library",
+    "/Network",
+    "/System",
+    "/Users",
+    "/Volumes",
+    "/cores",
+    "/opt",
+    "/private",
+    "/private/etc",
+    "/private/tmp",
+    "/private/var",
+    "/private/var/db",
+    "/private/var/db/receipts",
+];
+
+/// Prefixes under which nothing is removed.
+const DENIED_PREFIXES: &[&str] = &["/System", "/bin", "/sbin", "/usr"];
+
+/// Exceptions to [`DENIED_PREFIXES`].
+const ALLOWED_PREFIXES: &[&str] = &["/usr/local"];
+
+/// Folder names that several apps from the same vendor share directly under
+/// a Library category (`Application Support`, `Caches`, `Logs`, ...). Their
+/// children are fair game; the folder itself is not.
+const SHARED_VENDOR_DIRS: &[&str] = &[
+    "adobe",
+    "apple",
+    "google",
+    "jetbrains",
+    "microsoft",
+    "mozilla",
+    "oracle",
+];
+
+/// Deny-list/allow-list policy that decides whether a path may be trashed.
+#[derive(Clone, Debug)]
+pub struct RemovalPolicy {
+    home: Option<PathBuf>,
+}
+
+impl RemovalPolicy {
+    pub fn for_current_user() -> Self {
+        Self { home: home_dir() }
+    }
+
+    pub fn with_home(home: impl Into<PathBuf>) -> Self {
+        Self {
+            home: Some(home.into()),
+        }
+    }
+
+    /// The home directory this policy protects, if it is known.
+    pub fn home(&self) -> Option<&Path> {
+        self.home.as_deref()
+    }
+
+    pub fn check(&self, path: &Path) -> Result<(), Refusal> {
+        if !path.is_absolute()
+            || path
+                .components()
+                .any(|c| matches!(c, Component::ParentDir | Component::CurDir))
+        {
+            return Err(Refusal::NotAbsolute);
+        }
+
+        if DENIED_ROOTS.iter().any(|r| path == Path::new(r)) {
+            return Err(Refusal::Root);
+        }
+        if DENIED_PREFIXES.iter().any(|p| path.starts_with(p))
+            && !ALLOWED_PREFIXES.iter().any(|p| path.starts_with(p))
+        {
+            return Err(Refusal::SystemLocation);
+        }
+
+        // Library roots: `/Library`, `~/Library` and their direct children.
+        let mut library_roots = vec![PathBuf::from("/Library")];
+        if let Some(ref home) = self.home {
+            if path == home || path == home.join("Applications") {
+                return Err(Refusal::Root);
+            }
+            library_roots.push(home.join("Library"));
+        }
+        for lib in &library_roots {
+            if let Ok(rest) = path.strip_prefix(lib) {
+                let depth = rest.components().count();
+                if depth <= 1 {
+                    return Err(Refusal::LibraryRoot);
+                }
+                if depth == 2 && is_shared_vendor_dir(rest) {
+                    return Err(Refusal::SharedVendorFolder);
+                }
             }
-            Ok(())
+        }
+
+        if path.components().any(|c| {
+            c.as_os_str()
+                .to_str()
+                .is_some_and(|s| s.to_lowercase().starts_with("com.apple."))
+        }) {
+            return Err(Refusal::AppleBundle);
+        }
+
+        if let Some(ref home) = self.home {
+            if path.starts_with(home) {
+                if let Ok(resolved) = path.canonicalize() {
+                    let home_resolved = home.canonicalize().unwrap_or_else(|_| home.clone());
+                    if !resolved.starts_with(&home_resolved) {
+                        return Err(Refusal::EscapesHome);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn is_shared_vendor_dir(rest: &Path) -> bool {
+    rest.file_name()
+        .and_then(|s| s.to_str())
+        .is_some_and(|s| SHARED_VENDOR_DIRS.contains(&s.to_lowercase().as_str()))
+}
+
+/// Move `path` to the Trash, falling back to a permanent delete when the
+/// Trash is unavailable. Refuses (with [`CoreError::Protected`]) anything
+/// the [`RemovalPolicy`] rejects.
+pub fn move_to_trash_or_remove(path: &Path) -> Result<(), CoreError> {
+    remove_path(path, RemovalMode::TrashOrDelete)
+}
+
+/// Remove `path` the way `mode` says (see `Settings::removal_mode`), after
+/// the same [`RemovalPolicy`] check as [`move_to_trash_or_remove`].
+pub fn remove_path(path: &Path, mode: RemovalMode) -> Result<(), CoreError> {
+    RemovalPolicy::for_current_user()
+        .check(path)
+        .map_err(|r| CoreError::refused(path, r))?;
+    if mode != RemovalMode::Delete {
+        match trash::delete(path) {
+            Ok(_) => return Ok(()),
+            Err(e) if mode == RemovalMode::Trash => {
+                return Err(CoreError::TrashUnavailable {
+                    path: path.into(),
+                    message: e.to_string(),
+                });
+            }
+            Err(_trash_err) => {}
         }
     }
+    let meta = fs::symlink_metadata(path).map_err(|e| CoreError::io(path, e))?;
+    if meta.is_dir() {
+        fs::remove_dir_all(path).map_err(|e| CoreError::io(path, e))
+    } else {
+        fs::remove_file(path).map_err(|e| CoreError::io(path, e))
+    }
 }
 
-pub fn reveal_in_finder(path: &Path) -> Result<()> {
-    let p = path
-        .canonicalize()
-        .with_context(|| format!("Canonicalize {}", path.display()))?;
+pub fn reveal_in_finder(path: &Path) -> Result<(), CoreError> {
+    let p = path.canonicalize().map_err(|e| CoreError::io(path, e))?;
     std::process::Command::new("open")
         .arg("-R")
         .arg(p)
         .status()
-        .context("Failed to run `open -R`")?;
+        .map_err(|e| CoreError::other(format!("Failed to run `open -R`: {e}")))?;
     Ok(())
 }
 
@@ -70,4 +264,90 @@ mod tests {
             "/Users/alice/Library/Caches/x"
         )));
     }
+
+    fn alice() -> RemovalPolicy {
+        RemovalPolicy::with_home("/Users/alice")
+    }
+
+    #[test]
+    fn policy_refuses_roots_and_library_folders() {
+        let policy = alice();
+        for p in [
+            "/",
+            "/Library",
+            "/Applications",
+            "/Users/alice",
+            "/Users/alice/Library",
+            "/Users/alice/Library/Preferences",
+            "/Library/Application Support",
+            "/private/var/db/receipts",
+        ] {
+            assert!(policy.check(Path::new(p)).is_err(), "{p} should be refused");
+        }
+        assert_eq!(
+            policy.check(Path::new("/Users/alice/Library/Preferences")),
+            Err(Refusal::LibraryRoot)
+        );
+    }
+
+    #[test]
+    fn policy_refuses_system_apple_and_shared_paths() {
+        let policy = alice();
+        assert_eq!(
+            policy.check(Path::new("/System/Applications/Safari.app")),
+            Err(Refusal::SystemLocation)
+        );
+        assert_eq!(
+            policy.check(Path::new("/Users/alice/Library/Caches/com.apple.Safari")),
+            Err(Refusal::AppleBundle)
+        );
+        assert_eq!(
+            policy.check(Path::new("/Users/alice/Library/Application Support/Google")),
+            Err(Refusal::SharedVendorFolder)
+        );
+        assert_eq!(
+            policy.check(Path::new("relative/path")),
+            Err(Refusal::NotAbsolute)
+        );
+        assert_eq!(
+            policy.check(Path::new("/Users/alice/Library/Caches/../Preferences")),
+            Err(Refusal::NotAbsolute)
+        );
+    }
+
+    #[test]
+    fn policy_allows_app_leftovers() {
+        let policy = alice();
+        for p in [
+            "/Applications/Foo.app",
+            "/Users/alice/Library/Caches/com.example.foo",
+            "/Users/alice/Library/Application Support/Google/Chrome",
+            "/Library/Application Support/com.example.foo",
+            "/usr/local/bin/foo",
+        ] {
+            assert_eq!(policy.check(Path::new(p)), Ok(()), "{p} should be allowed");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn policy_refuses_symlinks_escaping_home() {
+        let root = std::env::temp_dir().join(format!("trash-policy-{}", std::process::id()));
+        let home = root.join("home");
+        let outside = root.join("outside");
+        fs::create_dir_all(home.join("Library").join("Caches")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        let link = home.join("Library").join("Caches").join("escape");
+        std::os::unix::fs::symlink(&outside, &link).unwrap();
+        let inside = home.join("Library").join("Caches").join("inside");
+        fs::create_dir_all(&inside).unwrap();
+
+        let policy = RemovalPolicy::with_home(&home);
+        let escaped = policy.check(&link);
+        let kept = policy.check(&inside);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(escaped, Err(Refusal::EscapesHome));
+        assert_eq!(kept, Ok(()));
+    }
 }
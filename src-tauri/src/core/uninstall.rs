@@ -0,0 +1,693 @@
+//! The uninstall pipeline: refusal checks, then the bundle, then related
+//! paths, reporting progress to a [`ProgressSink`].
+
+use std::{path::PathBuf, time::Duration};
+
+use super::{
+    apps::read_app,
+    backup::create_backup,
+    casks::{brew_uninstall_zap, cask_for_app, scan_casks, zap_paths},
+    error::CoreError,
+    exclusions::Exclusions,
+    plist_info::read_info_from_app,
+    progress::{ProgressKind, ProgressSink, Task},
+    protection::app_protection,
+    running::{is_app_running_simple, kill_app},
+    transaction::{Transaction, preflight},
+    trash::{is_protected_path, remove_path},
+};
+use crate::models::{
+    AppProtection, BackupFormat, FailureKind, KillPolicy, RemovalMode, Settings, UninstallFailure,
+    UninstallReport,
+};
+
+/// Per-call switches for [`run_uninstall`].
+#[derive(Clone, Debug)]
+pub struct UninstallOptions {
+    /// Allow removing Apple apps outside the sealed system volume.
+    pub allow_protected: bool,
+    /// Hand Homebrew cask installs to `brew uninstall --zap`.
+    pub via_brew: bool,
+    pub removal_mode: RemovalMode,
+    pub kill_policy: KillPolicy,
+    pub kill_deadline: Duration,
+    /// All-or-nothing removal; see [`crate::core::transaction`].
+    pub transactional: bool,
+    /// Archive the related paths into this folder before removing anything.
+    pub backup_dir: Option<PathBuf>,
+    pub backup_format: BackupFormat,
+}
+
+impl UninstallOptions {
+    /// Options from the user's settings; both overrides off, no backup.
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self {
+            allow_protected: false,
+            via_brew: false,
+            removal_mode: settings.removal_mode,
+            kill_policy: settings.kill_policy,
+            kill_deadline: settings.kill_deadline(),
+            transactional: settings.transactional_uninstall,
+            backup_dir: None,
+            backup_format: settings.backup_format,
+        }
+    }
+}
+
+/// How an uninstall checks for and quits the app's processes.
+struct UninstallHost<'a> {
+    is_running: &'a dyn Fn() -> bool,
+    /// Quit the app, returning how many processes were killed.
+    kill: &'a dyn Fn() -> u32,
+}
+
+/// Remove `app_path` and `related_paths`, reporting an `uninstall` task to
+/// `sink`.
+///
+/// Refuses protected and excluded apps, and Homebrew removals it can't
+/// honour, before touching anything, then running apps (after quitting
+/// them first if the kill policy says so). The bundle goes first and a
+/// failure there aborts. Related paths the removal policy flags as
+/// protected go next and abort on the first real error; the rest continue
+/// past per-item errors. Policy refusals and excluded paths are recorded
+/// as [`FailureKind::Refused`] without aborting.
+///
+/// With `options.transactional`, every path is pre-flighted first and any
+/// failure, at pre-flight or while staging, leaves the system as it was.
+/// The Homebrew route is refused in that mode since it can't be undone.
+///
+/// With `options.backup_dir`, the related paths (not the bundle, which can
+/// be reinstalled) are archived with the app's [`AppInfo`] first, and a
+/// failed backup aborts before anything is removed.
+///
+/// [`AppInfo`]: crate::models::AppInfo
+pub fn run_uninstall(
+    app_path: PathBuf,
+    app_name: String,
+    bundle_id: Option<String>,
+    related_paths: Vec<PathBuf>,
+    options: UninstallOptions,
+    exclusions: &Exclusions,
+    sink: &dyn ProgressSink,
+) -> Result<UninstallReport, CoreError> {
+    let (path, name, bid) = (&app_path, &app_name, bundle_id.as_deref());
+    let host = UninstallHost {
+        is_running: &|| is_app_running_simple(Some(path), bid, Some(name)),
+        kill: &|| kill_app(Some(path), bid, Some(name), options.kill_deadline),
+    };
+    run_uninstall_with(
+        &host,
+        app_path.clone(),
+        app_name.clone(),
+        bundle_id.clone(),
+        related_paths,
+        options.clone(),
+        exclusions,
+        sink,
+    )
+}
+
+/// [`run_uninstall`] against `host` instead of the live processes.
+#[allow(clippy::too_many_arguments)]
+fn run_uninstall_with(
+    host: &UninstallHost,
+    app_path: PathBuf,
+    app_name: String,
+    bundle_id: Option<String>,
+    related_paths: Vec<PathBuf>,
+    options: UninstallOptions,
+    exclusions: &Exclusions,
+    sink: &dyn ProgressSink,
+) -> Result<UninstallReport, CoreError> {
+    let task = Task::start(
+        sink,
+        ProgressKind::Uninstall,
+        format!("Starting uninstall of {app_name}..."),
+    );
+
+    let executable = read_info_from_app(&app_path)
+        .ok()
+        .and_then(|i| i.executable);
+    let abort = |err: CoreError| {
+        task.fail(err.to_string(), err.to_string());
+        Err(err)
+    };
+    let protected = |reason: String| CoreError::Protected {
+        path: Some(app_path.clone()),
+        reason,
+    };
+
+    let refusal = match app_protection(&app_path, bundle_id.as_deref(), executable.as_deref()) {
+        AppProtection::None => None,
+        AppProtection::Apple if options.allow_protected => None,
+        AppProtection::Apple => Some(format!(
+            "{app_name} is an Apple app. Abort uninstall (override required)."
+        )),
+        AppProtection::SealedSystem => Some(format!(
+            "{app_name} is part of macOS and cannot be removed."
+        )),
+    };
+    if let Some(reason) = refusal {
+        return abort(protected(reason));
+    }
+
+    if let Some(rule) = exclusions.matching_rule(&app_path) {
+        return abort(protected(format!(
+            "{app_name} is excluded by rule {rule:?}. Abort uninstall."
+        )));
+    }
+
+    // A cask install can be handed to Homebrew, which also clears the
+    // Caskroom entry and the zap paths. `--zap` can't be told to skip
+    // anything, so refuse if it would take an excluded path with it.
+    let cask = if options.via_brew {
+        cask_for_app(&scan_casks(), &app_path).cloned()
+    } else {
+        None
+    };
+    if cask.is_some() && options.transactional {
+        return abort(protected(
+            "brew --zap can't be rolled back. Turn off transactional uninstall or uninstall without Homebrew."
+                .into(),
+        ));
+    }
+    if let Some(c) = &cask {
+        if let Some(p) = zap_paths(c).into_iter().find(|p| exclusions.is_excluded(p)) {
+            return abort(CoreError::Protected {
+                reason: format!(
+                    "brew --zap would remove excluded {}. Uninstall without Homebrew instead.",
+                    p.display()
+                ),
+                path: Some(p),
+            });
+        }
+    }
+
+    // Only now that nothing refuses the uninstall may the app be quit.
+    if (host.is_running)() && options.kill_policy == KillPolicy::Kill {
+        task.progress(0.0, format!("Quitting {app_name}..."));
+        (host.kill)();
+    }
+    if (host.is_running)() {
+        return abort(CoreError::AppRunning {
+            name: app_name.clone(),
+            path: Some(app_path.clone()),
+        });
+    }
+
+    let mut report = UninstallReport {
+        app_path: app_path.clone(),
+        removed: Vec::new(),
+        failed: Vec::new(),
+        aborted: false,
+        backup: None,
+    };
+
+    // Last-line check: the frontend may hold a related list from before a
+    // rule was added.
+    let (excluded, mut related_paths): (Vec<PathBuf>, Vec<PathBuf>) = related_paths
+        .into_iter()
+        .partition(|p| exclusions.is_excluded(p));
+    for p in excluded {
+        let reason = format!(
+            "Excluded by rule {:?}",
+            exclusions.matching_rule(&p).unwrap_or_default()
+        );
+        report.failed.push(UninstallFailure {
+            path: p,
+            error: reason.clone(),
+            kind: FailureKind::Refused { reason },
+        });
+    }
+
+    if let Some(dir) = options
+        .backup_dir
+        .as_ref()
+        .filter(|_| !related_paths.is_empty())
+    {
+        task.progress(
+            0.0,
+            format!("Backing up {} item(s)...", related_paths.len()),
+        );
+        let app = read_app(&app_path);
+        match create_backup(
+            dir,
+            &app_name,
+            &related_paths,
+            Some(&app),
+            options.backup_format,
+        ) {
+            Ok(archive) => report.backup = Some(archive),
+            Err(e) => return abort(CoreError::from(e)),
+        }
+    }
+
+    if options.transactional {
+        return uninstall_transactional(&task, report, related_paths, options.removal_mode);
+    }
+
+    // `done` counts every item handled, removed or not.
+    let total_steps = 1 + related_paths.len();
+    let mut step = 0usize;
+    let advance = |step: usize, message: String, error: Option<String>| {
+        task.set_items(step as u64, Some(total_steps as u64));
+        task.report(step as f32 / total_steps as f32, message, error);
+    };
+
+    // Step 1: bundle itself.
+    let removed = match cask {
+        Some(c) => brew_uninstall_zap(&c.token)
+            .inspect(|_| {
+                related_paths.retain(|p| p.exists() || p.is_symlink());
+            })
+            .map_err(CoreError::from),
+        None => remove_path(&app_path, options.removal_mode),
+    };
+    if let Err(e) = removed {
+        let msg = format!("Failed to remove bundle: {e}");
+        task.fail(msg.clone(), msg);
+        report.aborted = true;
+        report.failed.push(failure(app_path.clone(), &e));
+        return Err(e);
+    }
+    step += 1;
+    report.removed.push(app_path.clone());
+    advance(step, format!("Removed {}", app_path.display()), None);
+
+    // Step 2: related paths, protected first.
+    remove_partitioned(
+        related_paths,
+        options.removal_mode,
+        &mut report,
+        &task,
+        |message, error| {
+            step += 1;
+            advance(step, message, error);
+        },
+    )?;
+
+    task.finish("Uninstall complete");
+    Ok(report)
+}
+
+/// Remove `paths` the way [`run_uninstall`] removes related files:
+/// paths the removal policy flags as protected first, aborting on the
+/// first real error, then the rest past per-item errors. `advance` is
+/// called once per item handled; an abort marks `report` and fails `task`.
+pub(crate) fn remove_partitioned(
+    paths: Vec<PathBuf>,
+    mode: RemovalMode,
+    report: &mut UninstallReport,
+    task: &Task,
+    mut advance: impl FnMut(String, Option<String>),
+) -> Result<(), CoreError> {
+    let (protected, unprotected): (Vec<PathBuf>, Vec<PathBuf>) =
+        paths.into_iter().partition(|p| is_protected_path(p));
+
+    // Protected — abort on first failure. Policy refusals are
+    // recorded and skipped: nothing was touched, so there is nothing to
+    // abort.
+    for p in protected {
+        match remove_path(&p, mode) {
+            Ok(()) => {
+                report.removed.push(p.clone());
+                advance(format!("Removed {}", p.display()), None);
+            }
+            Err(e @ CoreError::Protected { .. }) => {
+                let msg = format!("Refused to remove {}: {}", p.display(), e);
+                report.failed.push(failure(p, &e));
+                advance(msg, Some(e.to_string()));
+            }
+            Err(e) => {
+                let msg = format!("Aborting on {}: {e}", p.display());
+                report.aborted = true;
+                report.failed.push(failure(p, &e));
+                task.fail(msg.clone(), msg);
+                return Err(e);
+            }
+        }
+    }
+
+    // Unprotected — continue past per-item errors.
+    for p in unprotected {
+        match remove_path(&p, mode) {
+            Ok(()) => {
+                report.removed.push(p.clone());
+                advance(format!("Removed {}", p.display()), None);
+            }
+            Err(e) => {
+                let msg = format!("Failed to remove {}: {e}", p.display());
+                report.failed.push(failure(p, &e));
+                advance(msg, Some(e.to_string()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Pre-flight the bundle and every related path, stage them all, then
+/// commit. Nothing is touched when pre-flight fails, and a staging or
+/// commit failure puts back everything already moved. Paths inside another
+/// path in the set go with their ancestor.
+fn uninstall_transactional(
+    task: &Task,
+    mut report: UninstallReport,
+    related_paths: Vec<PathBuf>,
+    mode: RemovalMode,
+) -> Result<UninstallReport, CoreError> {
+    let mut all = vec![report.app_path.clone()];
+    all.extend(related_paths);
+    all.sort();
+    all.dedup();
+    let (nested, top): (Vec<PathBuf>, Vec<PathBuf>) = all
+        .iter()
+        .cloned()
+        .partition(|p| all.iter().any(|a| a != p && p.starts_with(a)));
+    // Bundle first, as in the regular run.
+    let (bundle, rest): (Vec<PathBuf>, Vec<PathBuf>) =
+        top.into_iter().partition(|p| *p == report.app_path);
+    let top: Vec<PathBuf> = bundle.into_iter().chain(rest).collect();
+
+    let mut issues: Vec<(PathBuf, CoreError)> = top
+        .iter()
+        .filter_map(|p| preflight(p).err().map(|e| (p.clone(), e)))
+        .collect();
+    if !issues.is_empty() {
+        for (p, e) in &issues {
+            task.report(
+                0.0,
+                format!("Pre-flight failed for {}", p.display()),
+                Some(e.to_string()),
+            );
+        }
+        let msg = format!(
+            "{} path(s) failed pre-flight. Nothing was removed.",
+            issues.len()
+        );
+        task.fail(msg, issues[0].1.to_string());
+        return Err(issues.swap_remove(0).1);
+    }
+
+    // Staging is the first half of the work, commit the second.
+    let total_steps = 2 * top.len();
+    let advance = |step: usize, message: String, error: Option<String>| {
+        task.set_items(step as u64, Some(total_steps as u64));
+        task.report(step as f32 / total_steps as f32, message, error);
+    };
+
+    let mut tx = Transaction::new();
+    for p in &top {
+        if let Err(e) = tx.stage(p) {
+            let staged = tx.len();
+            let unrestored = tx.rollback();
+            let msg = rollback_message(
+                format!("Failed to stage {}: {e}.", p.display()),
+                staged,
+                &unrestored,
+            );
+            task.fail(msg, e.to_string());
+            return Err(e);
+        }
+        advance(tx.len(), format!("Staged {}", p.display()), None);
+    }
+
+    let mut step = top.len();
+    let committed = tx.commit(mode, |p, result| {
+        step += 1;
+        match result {
+            Ok(()) => {
+                advance(step, format!("Removed {}", p.display()), None);
+                let inside = nested.iter().filter(|n| n.starts_with(&p)).cloned();
+                report.removed.push(p.clone());
+                report.removed.extend(inside);
+            }
+            Err(e) => {
+                advance(
+                    step,
+                    format!("Failed to remove {}: {e}", p.display()),
+                    Some(e.to_string()),
+                );
+                report.failed.push(failure(p, &e));
+            }
+        }
+    });
+    if let Err(f) = committed {
+        let msg = rollback_message(
+            format!(
+                "Failed to move {} to the Trash: {}.",
+                f.path.display(),
+                f.error
+            ),
+            top.len(),
+            &f.unrestored,
+        );
+        task.fail(msg, f.error.to_string());
+        return Err(f.error);
+    }
+
+    task.finish("Uninstall complete");
+    Ok(report)
+}
+
+/// `cause` plus how many of `total` items were put back and which could
+/// not be.
+fn rollback_message(cause: String, total: usize, unrestored: &[(PathBuf, CoreError)]) -> String {
+    let mut msg = format!("{cause} Rolled back {} item(s).", total - unrestored.len());
+    if !unrestored.is_empty() {
+        let paths: Vec<String> = unrestored
+            .iter()
+            .map(|(p, e)| {
+                log::error!("Rollback failed for {}: {e}", p.display());
+                p.display().to_string()
+            })
+            .collect();
+        msg.push_str(&format!(" Could not restore: {}", paths.join(", ")));
+    }
+    msg
+}
+
+/// Build the report entry for a failed removal, surfacing policy refusals
+/// as [`FailureKind::Refused`].
+pub fn failure(path: PathBuf, e: &CoreError) -> UninstallFailure {
+    let kind = match e {
+        CoreError::Protected { reason, .. } => FailureKind::Refused {
+            reason: reason.clone(),
+        },
+        _ => FailureKind::Error,
+    };
+    UninstallFailure {
+        path,
+        error: e.to_string(),
+        kind,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::progress::{ChannelSink, NullSink};
+    use crate::models::ExclusionRules;
+    use std::fs;
+
+    #[test]
+    fn reports_each_removal_in_order() {
+        let root = std::env::temp_dir().join(format!("uninstall-test-{}", std::process::id()));
+        let app = root.join("UninstallTestApp.app");
+        let cache = root.join("cache");
+        let kept = root.join("kept");
+        for dir in [&app, &cache, &kept] {
+            fs::create_dir_all(dir).unwrap();
+        }
+        let rules = ExclusionRules {
+            paths: vec![kept.to_string_lossy().into_owned()],
+            ..Default::default()
+        };
+        let exclusions = Exclusions::compile(&rules, &root).unwrap();
+        let options = UninstallOptions {
+            removal_mode: RemovalMode::Delete,
+            ..UninstallOptions::from_settings(&Settings::default())
+        };
+
+        let (sink, rx) = ChannelSink::new();
+        let report = run_uninstall(
+            app.clone(),
+            "UninstallTestApp".into(),
+            None,
+            vec![cache.clone(), kept.clone()],
+            options,
+            &exclusions,
+            &sink,
+        );
+        let kept_exists = kept.exists();
+        fs::remove_dir_all(&root).unwrap();
+
+        let report = report.unwrap();
+        assert_eq!(report.removed, vec![app.clone(), cache.clone()]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].path, kept);
+        assert!(matches!(report.failed[0].kind, FailureKind::Refused { .. }));
+        assert!(kept_exists);
+
+        let events: Vec<_> = rx
+            .try_iter()
+            .map(|e| (e.seq, e.message, e.done, e.total, e.finished))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                (
+                    0,
+                    "Starting uninstall of UninstallTestApp...".to_string(),
+                    None,
+                    None,
+                    false
+                ),
+                (
+                    1,
+                    format!("Removed {}", app.display()),
+                    Some(1),
+                    Some(2),
+                    false
+                ),
+                (
+                    2,
+                    format!("Removed {}", cache.display()),
+                    Some(2),
+                    Some(2),
+                    false
+                ),
+                (3, "Uninstall complete".to_string(), Some(2), Some(2), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn transactional_run_touches_nothing_on_preflight_failure() {
+        let root = std::env::temp_dir().join(format!("uninstall-txn-{}", std::process::id()));
+        let app = root.join("UninstallTxnApp.app");
+        let prefs = root.join("prefs");
+        fs::create_dir_all(app.join("Contents")).unwrap();
+        fs::create_dir_all(&prefs).unwrap();
+        let options = UninstallOptions {
+            removal_mode: RemovalMode::Delete,
+            transactional: true,
+            ..UninstallOptions::from_settings(&Settings::default())
+        };
+        let run = |related: Vec<PathBuf>| {
+            run_uninstall(
+                app.clone(),
+                "UninstallTxnApp".into(),
+                None,
+                related,
+                options.clone(),
+                &Exclusions::default(),
+                &NullSink,
+            )
+        };
+
+        let refused = run(vec![prefs.clone(), root.join("gone")]);
+        let untouched = app.exists() && prefs.exists();
+        // The bundle's own folder is covered by the bundle.
+        let done = run(vec![prefs.clone(), app.join("Contents")]);
+        let leftovers = fs::read_dir(&root).unwrap().count();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(matches!(refused, Err(CoreError::NotFound { .. })));
+        assert!(untouched);
+        let done = done.unwrap();
+        assert_eq!(done.removed, vec![app.clone(), app.join("Contents"), prefs]);
+        assert!(done.failed.is_empty());
+        assert_eq!(leftovers, 0);
+    }
+
+    #[test]
+    fn refuses_excluded_app_before_quitting_it() {
+        let root = std::env::temp_dir().join(format!("uninstall-kill-{}", std::process::id()));
+        let app = root.join("UninstallKillApp.app");
+        fs::create_dir_all(&app).unwrap();
+        let rules = ExclusionRules {
+            paths: vec![app.to_string_lossy().into_owned()],
+            ..Default::default()
+        };
+        let exclusions = Exclusions::compile(&rules, &root).unwrap();
+        let options = UninstallOptions {
+            kill_policy: KillPolicy::Kill,
+            ..UninstallOptions::from_settings(&Settings::default())
+        };
+        let kills = std::cell::Cell::new(0);
+        let host = UninstallHost {
+            is_running: &|| true,
+            kill: &|| {
+                kills.set(kills.get() + 1);
+                1
+            },
+        };
+        let run = |exclusions: &Exclusions| {
+            run_uninstall_with(
+                &host,
+                app.clone(),
+                "UninstallKillApp".into(),
+                None,
+                Vec::new(),
+                options.clone(),
+                exclusions,
+                &NullSink,
+            )
+        };
+
+        let excluded = run(&exclusions);
+        let kills_when_excluded = kills.get();
+        let running = run(&Exclusions::default());
+        let still_there = app.exists();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(matches!(excluded, Err(CoreError::Protected { .. })));
+        assert_eq!(kills_when_excluded, 0);
+        assert!(matches!(running, Err(CoreError::AppRunning { .. })));
+        assert_eq!(kills.get(), 1, "quit once nothing refused it");
+        assert!(still_there);
+    }
+
+    #[test]
+    fn backs_up_related_paths_before_removal() {
+        let root = std::env::temp_dir().join(format!("uninstall-bak-{}", std::process::id()));
+        let app = root.join("UninstallBakApp.app");
+        let prefs = root.join("Preferences").join("com.bak.plist");
+        fs::create_dir_all(&app).unwrap();
+        fs::create_dir_all(prefs.parent().unwrap()).unwrap();
+        fs::write(&prefs, "plist").unwrap();
+        let options = UninstallOptions {
+            removal_mode: RemovalMode::Delete,
+            backup_dir: Some(root.join("backups")),
+            ..UninstallOptions::from_settings(&Settings::default())
+        };
+
+        let report = run_uninstall(
+            app.clone(),
+            "UninstallBakApp".into(),
+            None,
+            vec![prefs.clone()],
+            options,
+            &Exclusions::default(),
+            &NullSink,
+        )
+        .unwrap();
+        let removed = !prefs.exists();
+        let archive = report.backup.clone().unwrap();
+        let manifest = crate::core::backup::read_manifest(&archive).unwrap();
+        let restored = crate::core::backup::restore_backup_with(
+            &archive,
+            &crate::core::trash::RemovalPolicy::with_home(&root),
+        )
+        .unwrap();
+        let contents = fs::read_to_string(&prefs).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(removed);
+        assert_eq!(report.removed, vec![app.clone(), prefs.clone()]);
+        assert_eq!(manifest.app.unwrap().path, app);
+        assert_eq!(restored.restored, vec![prefs]);
+        assert_eq!(contents, "plist");
+    }
+}
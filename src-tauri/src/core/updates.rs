@@ -0,0 +1,488 @@
+//! Check Sparkle appcasts for available updates.
+//!
+//! Apps that embed Sparkle declare their feed as `SUFeedURL` in
+//! `Info.plist`. The feed is an RSS document whose `<item>`s describe
+//! releases; the version lives either in `sparkle:*` child elements
+//! (Sparkle 2) or as attributes of `<enclosure>` (Sparkle 1). We pick the
+//! newest item the running macOS can install and compare it against the
+//! installed version with [`AppVersion`].
+//!
+//! Fetching goes through [`HttpClient`] so tests can point the checker at a
+//! local stand-in server. The default [`CurlClient`] shells out to
+//! `/usr/bin/curl`, which ships with every macOS install.
+
+use anyhow::{Context, Result, bail};
+use quick_xml::{Reader, events::Event};
+use std::process::Command;
+
+use super::{plist_info::read_info_from_app, version::AppVersion};
+use crate::models::{AppInfo, UpdateCheck};
+
+/// Minimal blocking HTTP GET.
+pub trait HttpClient: Send + Sync {
+    fn get(&self, url: &str) -> Result<Vec<u8>>;
+}
+
+/// [`HttpClient`] backed by the system `curl`.
+pub struct CurlClient {
+    pub timeout_secs: u32,
+}
+
+impl Default for CurlClient {
+    fn default() -> Self {
+        Self { timeout_secs: 15 }
+    }
+}
+
+impl HttpClient for CurlClient {
+    fn get(&self, url: &str) -> Result<Vec<u8>> {
+        if !(url.starts_with("https://") || url.starts_with("http://")) {
+            bail!("Unsupported feed URL: {url}");
+        }
+        let out = Command::new("curl")
+            .args(["--silent", "--show-error", "--fail", "--location"])
+            .args(["--max-time", &self.timeout_secs.to_string()])
+            .arg("--")
+            .arg(url)
+            .output()
+            .context("Failed to run curl")?;
+        if !out.status.success() {
+            bail!(
+                "GET {url} failed: {}",
+                String::from_utf8_lossy(&out.stderr).trim()
+            );
+        }
+        Ok(out.stdout)
+    }
+}
+
+/// One release from an appcast.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AppcastItem {
+    pub title: Option<String>,
+    /// `sparkle:version`, compared against `CFBundleVersion`.
+    pub version: Option<String>,
+    /// `sparkle:shortVersionString`, compared against
+    /// `CFBundleShortVersionString`.
+    pub short_version: Option<String>,
+    pub minimum_system_version: Option<String>,
+    pub download_url: Option<String>,
+    pub release_notes_url: Option<String>,
+    /// `sparkle:channel`; items on a named channel (e.g. "beta") are
+    /// opt-in and skipped by the checker.
+    pub channel: Option<String>,
+}
+
+/// Parse the `<item>`s of a Sparkle appcast.
+pub fn parse_appcast(xml: &[u8]) -> Result<Vec<AppcastItem>> {
+    let mut reader = Reader::from_reader(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut items = Vec::new();
+    let mut current: Option<AppcastItem> = None;
+    let mut element: Option<String> = None;
+    let mut text = String::new();
+    let mut buf = Vec::new();
+
+    loop {
+        match reader
+            .read_event_into(&mut buf)
+            .context("Malformed appcast XML")?
+        {
+            Event::Start(e) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+                if name == "item" {
+                    current = Some(AppcastItem::default());
+                } else if current.is_some() {
+                    if name == "enclosure" {
+                        read_enclosure(&e, current.as_mut().unwrap());
+                    }
+                    element = Some(name);
+                    text.clear();
+                }
+            }
+            Event::Empty(e) => {
+                if let Some(item) = current.as_mut() {
+                    if e.name().as_ref() == b"enclosure" {
+                        read_enclosure(&e, item);
+                    }
+                }
+            }
+            // Text is collected raw and unescaped once the element ends, so
+            // entity references split out as separate events rejoin it.
+            Event::Text(t) if element.is_some() => {
+                text.push_str(&t.decode().unwrap_or_default());
+            }
+            Event::CData(t) if element.is_some() => {
+                text.push_str(&quick_xml::escape::escape(
+                    t.decode().unwrap_or_default().into_owned(),
+                ));
+            }
+            Event::GeneralRef(r) if element.is_some() => {
+                text.push('&');
+                text.push_str(&r.decode().unwrap_or_default());
+                text.push(';');
+            }
+            Event::End(e) => {
+                if e.name().as_ref() == b"item" {
+                    items.extend(current.take());
+                } else if let (Some(item), Some(name)) = (current.as_mut(), element.take()) {
+                    let value = quick_xml::escape::unescape(text.trim())
+                        .map(|s| s.into_owned())
+                        .unwrap_or_else(|_| text.trim().to_string());
+                    apply_element(item, &name, value);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(items)
+}
+
+fn apply_element(item: &mut AppcastItem, name: &str, value: String) {
+    if value.is_empty() {
+        return;
+    }
+    match name {
+        "title" => item.title = Some(value),
+        "sparkle:version" => item.version = Some(value),
+        "sparkle:shortVersionString" => item.short_version = Some(value),
+        "sparkle:minimumSystemVersion" => item.minimum_system_version = Some(value),
+        "sparkle:releaseNotesLink" | "sparkle:fullReleaseNotesLink" => {
+            item.release_notes_url.get_or_insert(value);
+        }
+        "sparkle:channel" => item.channel = Some(value),
+        "link" => {
+            item.download_url.get_or_insert(value);
+        }
+        _ => {}
+    }
+}
+
+/// Sparkle 1 puts the version and download URL on `<enclosure>`.
+fn read_enclosure(e: &quick_xml::events::BytesStart<'_>, item: &mut AppcastItem) {
+    for attr in e.attributes().flatten() {
+        let Ok(value) = attr.unescape_value() else {
+            continue;
+        };
+        let value = value.into_owned();
+        match attr.key.as_ref() {
+            b"url" => item.download_url = Some(value),
+            b"sparkle:version" => {
+                item.version.get_or_insert(value);
+            }
+            b"sparkle:shortVersionString" => {
+                item.short_version.get_or_insert(value);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// True if `item` is newer than the installed `(short, build)` version.
+/// Sparkle compares `sparkle:version` with `CFBundleVersion`; fall back to
+/// the short versions when either side lacks a build number.
+pub fn is_newer(item: &AppcastItem, short: Option<&str>, build: Option<&str>) -> bool {
+    match (item.version.as_deref(), build) {
+        (Some(remote), Some(local)) => AppVersion::parse(remote) > AppVersion::parse(local),
+        _ => match (
+            item.short_version.as_deref().or(item.version.as_deref()),
+            short,
+        ) {
+            (Some(remote), Some(local)) => AppVersion::parse(remote) > AppVersion::parse(local),
+            _ => false,
+        },
+    }
+}
+
+/// Newest default-channel item installable on `os_version` (e.g. "14.5").
+/// Items without a minimum system version are always eligible.
+pub fn latest_item<'a>(
+    items: &'a [AppcastItem],
+    os_version: Option<&str>,
+) -> Option<&'a AppcastItem> {
+    let os = os_version.map(AppVersion::parse);
+    items
+        .iter()
+        .filter(|i| i.channel.is_none())
+        .filter(|i| match (&os, i.minimum_system_version.as_deref()) {
+            (Some(os), Some(min)) => AppVersion::parse(min) <= *os,
+            _ => true,
+        })
+        .max_by(|a, b| {
+            let key = |i: &AppcastItem| {
+                AppVersion::from_bundle(i.short_version.as_deref(), i.version.as_deref())
+            };
+            key(a).cmp(&key(b))
+        })
+}
+
+/// Check one app. `None` if it has no `SUFeedURL`.
+pub fn check_app_update(
+    client: &dyn HttpClient,
+    app: &AppInfo,
+    os_version: Option<&str>,
+) -> Option<UpdateCheck> {
+    let feed_url = read_info_from_app(&app.path).ok()?.feed_url?;
+    let mut check = UpdateCheck {
+        app_path: app.path.clone(),
+        name: app.name.clone(),
+        feed_url: feed_url.clone(),
+        installed_version: app.version.clone(),
+        latest_version: None,
+        latest_build: None,
+        minimum_system_version: None,
+        download_url: None,
+        release_notes_url: None,
+        update_available: false,
+        error: None,
+    };
+
+    let items = match client.get(&feed_url).and_then(|body| parse_appcast(&body)) {
+        Ok(items) => items,
+        Err(e) => {
+            check.error = Some(format!("{e:#}"));
+            return Some(check);
+        }
+    };
+    if let Some(latest) = latest_item(&items, os_version) {
+        check.update_available =
+            is_newer(latest, app.version.as_deref(), app.build_version.as_deref());
+        check.latest_version = latest
+            .short_version
+            .clone()
+            .or_else(|| latest.version.clone());
+        check.latest_build = latest.version.clone();
+        check.minimum_system_version = latest.minimum_system_version.clone();
+        check.download_url = latest.download_url.clone();
+        check.release_notes_url = latest.release_notes_url.clone();
+    }
+    Some(check)
+}
+
+/// Check every app that declares a Sparkle feed. `on_progress` receives
+/// `(apps done, app count, app name)` before each app.
+pub fn check_updates<F>(
+    client: &dyn HttpClient,
+    apps: &[AppInfo],
+    os_version: Option<&str>,
+    mut on_progress: F,
+) -> Vec<UpdateCheck>
+where
+    F: FnMut(usize, usize, &str),
+{
+    apps.iter()
+        .enumerate()
+        .filter_map(|(i, app)| {
+            on_progress(i, apps.len(), &app.name);
+            check_app_update(client, app, os_version)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        collections::HashMap,
+        fs,
+        io::{Read, Write},
+        net::TcpListener,
+        path::{Path, PathBuf},
+        thread,
+    };
+
+    const SPARKLE2_FEED: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<rss version="2.0" xmlns:sparkle="http://www.andymatuschak.org/xml-namespaces/sparkle">
+  <channel>
+    <title>Example</title>
+    <item>
+      <title>Version 2.1</title>
+      <sparkle:version>2100</sparkle:version>
+      <sparkle:shortVersionString>2.1</sparkle:shortVersionString>
+      <sparkle:minimumSystemVersion>13.0</sparkle:minimumSystemVersion>
+      <sparkle:releaseNotesLink>https://example.com/notes?v=2.1&amp;lang=en</sparkle:releaseNotesLink>
+      <enclosure url="https://example.com/Example-2.1.zip" length="1" type="application/octet-stream"/>
+    </item>
+    <item>
+      <title>Version 3.0 beta</title>
+      <sparkle:channel>beta</sparkle:channel>
+      <sparkle:version>3000</sparkle:version>
+      <sparkle:shortVersionString>3.0b1</sparkle:shortVersionString>
+      <enclosure url="https://example.com/Example-3.0b1.zip" length="1" type="application/octet-stream"/>
+    </item>
+    <item>
+      <title>Version 2.2</title>
+      <sparkle:version>2200</sparkle:version>
+      <sparkle:shortVersionString>2.2</sparkle:shortVersionString>
+      <sparkle:minimumSystemVersion>15.0</sparkle:minimumSystemVersion>
+      <enclosure url="https://example.com/Example-2.2.zip" length="1" type="application/octet-stream"/>
+    </item>
+  </channel>
+</rss>"#;
+
+    const SPARKLE1_FEED: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<rss version="2.0" xmlns:sparkle="http://www.andymatuschak.org/xml-namespaces/sparkle">
+  <channel>
+    <item>
+      <title>1.4.2</title>
+      <enclosure url="https://example.com/Legacy-1.4.2.dmg" sparkle:version="142" sparkle:shortVersionString="1.4.2" length="1" type="application/octet-stream"/>
+    </item>
+  </channel>
+</rss>"#;
+
+    #[test]
+    fn parses_sparkle2_elements() {
+        let items = parse_appcast(SPARKLE2_FEED.as_bytes()).unwrap();
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].version.as_deref(), Some("2100"));
+        assert_eq!(items[0].short_version.as_deref(), Some("2.1"));
+        assert_eq!(items[0].minimum_system_version.as_deref(), Some("13.0"));
+        assert_eq!(
+            items[0].release_notes_url.as_deref(),
+            Some("https://example.com/notes?v=2.1&lang=en")
+        );
+        assert_eq!(
+            items[0].download_url.as_deref(),
+            Some("https://example.com/Example-2.1.zip")
+        );
+        assert_eq!(items[1].channel.as_deref(), Some("beta"));
+    }
+
+    #[test]
+    fn parses_sparkle1_enclosure_attributes() {
+        let items = parse_appcast(SPARKLE1_FEED.as_bytes()).unwrap();
+        assert_eq!(items[0].version.as_deref(), Some("142"));
+        assert_eq!(items[0].short_version.as_deref(), Some("1.4.2"));
+        assert!(is_newer(&items[0], Some("1.4.1"), Some("141")));
+        assert!(!is_newer(&items[0], Some("1.4.2"), Some("142")));
+    }
+
+    #[test]
+    fn latest_item_respects_channel_and_minimum_os() {
+        let items = parse_appcast(SPARKLE2_FEED.as_bytes()).unwrap();
+        let on_sonoma = latest_item(&items, Some("14.5")).unwrap();
+        assert_eq!(on_sonoma.short_version.as_deref(), Some("2.1"));
+        let on_sequoia = latest_item(&items, Some("15.1")).unwrap();
+        assert_eq!(on_sequoia.short_version.as_deref(), Some("2.2"));
+    }
+
+    /// Serve `body` once over HTTP on a random local port.
+    fn serve_once(body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            if let Ok((mut stream, _)) = listener.accept() {
+                let mut req = [0u8; 1024];
+                let _ = stream.read(&mut req);
+                let resp = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/rss+xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(resp.as_bytes());
+            }
+        });
+        format!("http://{addr}/appcast.xml")
+    }
+
+    /// Serves canned bodies by URL; anything else fails like an
+    /// unreachable host.
+    struct FakeClient(HashMap<&'static str, &'static str>);
+
+    impl HttpClient for FakeClient {
+        fn get(&self, url: &str) -> Result<Vec<u8>> {
+            match self.0.get(url) {
+                Some(body) => Ok(body.as_bytes().to_vec()),
+                None => bail!("GET {url} failed: Could not resolve host"),
+            }
+        }
+    }
+
+    /// A bundle at `root/<name>.app` whose `Info.plist` declares `feed`.
+    fn fixture_app(root: &Path, name: &str, feed: Option<&str>) -> AppInfo {
+        let path = root.join(format!("{name}.app"));
+        let contents = path.join("Contents");
+        fs::create_dir_all(&contents).unwrap();
+        let feed = feed
+            .map(|f| format!("<key>SUFeedURL</key><string>{f}</string>"))
+            .unwrap_or_default();
+        fs::write(
+            contents.join("Info.plist"),
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0"><dict>
+<key>CFBundleShortVersionString</key><string>2.1</string>
+<key>CFBundleVersion</key><string>2100</string>
+{feed}
+</dict></plist>"#
+            ),
+        )
+        .unwrap();
+        AppInfo {
+            path,
+            name: name.to_string(),
+            version: Some("2.1".into()),
+            build_version: Some("2100".into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn checks_apps_through_the_client() {
+        let root = std::env::temp_dir().join(format!("updates-test-{}", std::process::id()));
+        let client = FakeClient(HashMap::from([(
+            "https://example.com/appcast.xml",
+            SPARKLE2_FEED,
+        )]));
+        let example = fixture_app(&root, "Example", Some("https://example.com/appcast.xml"));
+        let offline = fixture_app(&root, "Offline", Some("https://down.example.com/feed"));
+        let no_feed = fixture_app(&root, "Plain", None);
+
+        let on_sonoma = check_app_update(&client, &example, Some("14.5")).unwrap();
+        let on_sequoia = check_app_update(&client, &example, Some("15.1")).unwrap();
+        let failed = check_app_update(&client, &offline, Some("15.1")).unwrap();
+        let mut progress = Vec::new();
+        let all = check_updates(
+            &client,
+            &[example.clone(), no_feed.clone(), offline.clone()],
+            Some("15.1"),
+            |done, total, name| progress.push((done, total, name.to_string())),
+        );
+        let skipped = check_app_update(&client, &no_feed, None);
+        fs::remove_dir_all(&root).unwrap();
+
+        // 2.2 needs macOS 15, so Sonoma stays on the installed 2.1.
+        assert!(!on_sonoma.update_available);
+        assert_eq!(on_sonoma.latest_version.as_deref(), Some("2.1"));
+        assert!(on_sequoia.update_available);
+        assert_eq!(on_sequoia.latest_version.as_deref(), Some("2.2"));
+        assert_eq!(on_sequoia.latest_build.as_deref(), Some("2200"));
+        assert_eq!(on_sequoia.minimum_system_version.as_deref(), Some("15.0"));
+        assert_eq!(
+            on_sequoia.download_url.as_deref(),
+            Some("https://example.com/Example-2.2.zip")
+        );
+        assert!(on_sequoia.error.is_none());
+
+        assert!(!failed.update_available);
+        assert!(failed.latest_version.is_none());
+        assert!(failed.error.unwrap().contains("Could not resolve host"));
+
+        assert!(skipped.is_none());
+        let paths: Vec<PathBuf> = all.into_iter().map(|c| c.app_path).collect();
+        assert_eq!(paths, vec![example.path, offline.path]);
+        assert_eq!(progress.len(), 3);
+        assert_eq!(progress[2], (2, 3, "Offline".to_string()));
+    }
+
+    #[test]
+    fn curl_client_fetches_from_local_server() {
+        let url = serve_once(SPARKLE1_FEED);
+        let body = CurlClient::default().get(&url).unwrap();
+        let items = parse_appcast(&body).unwrap();
+        assert_eq!(items[0].short_version.as_deref(), Some("1.4.2"));
+    }
+}
@@ -0,0 +1,165 @@
+//! Estimate when an app was last used.
+//!
+//! `AppInfo.modified_at` is the bundle's mtime, which moves on every update
+//! and says nothing about usage. The authoritative signal is the
+//! `com.apple.lastuseddate#PS` extended attribute LaunchServices writes on
+//! launch (it backs Spotlight's `kMDItemLastUsedDate`), or the older
+//! `com.apple.metadata:kMDItemLastUsedDate` binary plist. When the bundle
+//! has neither, we take the newest of:
+//!
+//! - the bundle's access time,
+//! - the mtime of `~/Library/Preferences/<bundle_id>.plist`,
+//! - the mtime of the app's `~/Library/Application Support` folder and its
+//!   direct children.
+//!
+//! Those are only a fallback: Spotlight, Time Machine or Finder reading
+//! the bundle bump its access time without the app running, so they would
+//! otherwise hide a genuinely stale last-used date.
+//!
+//! Every signal is a single `stat`/`getxattr` or one `read_dir`, so this runs
+//! during `scan_apps` without walking anything recursively.
+
+use home::home_dir;
+use std::{
+    fs::{self, Metadata},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::models::AppInfo;
+
+const XATTR_LAST_USED: &str = "com.apple.lastuseddate#PS";
+const XATTR_MD_LAST_USED: &str = "com.apple.metadata:kMDItemLastUsedDate";
+
+fn unix_secs(t: SystemTime) -> Option<i64> {
+    t.duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_secs() as i64)
+}
+
+fn mtime(path: &Path) -> Option<i64> {
+    fs::metadata(path)
+        .ok()
+        .and_then(|m| m.modified().ok())
+        .and_then(unix_secs)
+}
+
+/// Decode `com.apple.lastuseddate#PS`: a `struct timespec` with
+/// little-endian 64-bit seconds followed by nanoseconds.
+pub fn parse_last_used_xattr(raw: &[u8]) -> Option<i64> {
+    let secs: [u8; 8] = raw.get(..8)?.try_into().ok()?;
+    let secs = i64::from_le_bytes(secs);
+    (secs > 0).then_some(secs)
+}
+
+/// Decode `com.apple.metadata:kMDItemLastUsedDate`: a binary plist holding
+/// a single date.
+pub fn parse_md_last_used(raw: &[u8]) -> Option<i64> {
+    let v = plist::Value::from_reader(std::io::Cursor::new(raw)).ok()?;
+    v.as_date().map(SystemTime::from).and_then(unix_secs)
+}
+
+fn xattr_last_used(app_path: &Path) -> Option<i64> {
+    let ps = xattr::get(app_path, XATTR_LAST_USED)
+        .ok()
+        .flatten()
+        .and_then(|raw| parse_last_used_xattr(&raw));
+    let md = xattr::get(app_path, XATTR_MD_LAST_USED)
+        .ok()
+        .flatten()
+        .and_then(|raw| parse_md_last_used(&raw));
+    ps.max(md)
+}
+
+/// Newest mtime among `dir` itself and its direct children.
+fn newest_in_dir(dir: &Path) -> Option<i64> {
+    let own = mtime(dir)?;
+    let children = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter_map(|e| e.metadata().ok())
+        .filter_map(|m| m.modified().ok())
+        .filter_map(unix_secs)
+        .max();
+    Some(own.max(children.unwrap_or(own)))
+}
+
+/// The LaunchServices date when there is one, else the newest fallback
+/// signal.
+fn pick_last_used(xattr: Option<i64>, fallbacks: Vec<Option<i64>>) -> Option<i64> {
+    xattr.or_else(|| fallbacks.into_iter().flatten().max())
+}
+
+/// Best estimate of when the app at `app_path` was last used, as a Unix
+/// timestamp. `bundle_meta` is the bundle's already-fetched metadata.
+pub fn last_used_at(
+    app_path: &Path,
+    bundle_meta: Option<&Metadata>,
+    bundle_id: Option<&str>,
+    app_name: Option<&str>,
+) -> Option<i64> {
+    if let Some(t) = xattr_last_used(app_path) {
+        return Some(t);
+    }
+    let mut signals = vec![
+        bundle_meta
+            .and_then(|m| m.accessed().ok())
+            .and_then(unix_secs),
+    ];
+
+    if let Some(home) = home_dir() {
+        let lib = home.join("Library");
+        if let Some(bid) = bundle_id {
+            signals.push(mtime(&lib.join("Preferences").join(format!("{bid}.plist"))));
+            signals.push(newest_in_dir(&lib.join("Application Support").join(bid)));
+        }
+        if let Some(name) = app_name {
+            signals.push(newest_in_dir(&lib.join("Application Support").join(name)));
+        }
+    }
+
+    pick_last_used(None, signals)
+}
+
+/// True if the app has a usage estimate and it is older than `cutoff`
+/// (Unix seconds). Apps without any signal are not reported as unused.
+pub fn is_unused_since(app: &AppInfo, cutoff: i64) -> bool {
+    app.last_used_at.is_some_and(|t| t < cutoff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_lastuseddate_timespec() {
+        let mut raw = 1_700_000_000i64.to_le_bytes().to_vec();
+        raw.extend(123_456_789i64.to_le_bytes());
+        assert_eq!(parse_last_used_xattr(&raw), Some(1_700_000_000));
+        assert_eq!(parse_last_used_xattr(&[1, 2, 3]), None);
+    }
+
+    #[test]
+    fn decodes_md_last_used_plist() {
+        let date = plist::Date::from(UNIX_EPOCH + std::time::Duration::from_secs(1_600_000_000));
+        let mut raw = Vec::new();
+        plist::Value::Date(date).to_writer_binary(&mut raw).unwrap();
+        assert_eq!(parse_md_last_used(&raw), Some(1_600_000_000));
+    }
+
+    #[test]
+    fn last_used_xattr_outranks_newer_atime() {
+        let launched = Some(1_600_000_000);
+        let indexed_atime = Some(1_700_000_000);
+        let prefs_mtime = Some(1_650_000_000);
+        assert_eq!(
+            pick_last_used(launched, vec![indexed_atime, prefs_mtime]),
+            launched
+        );
+        assert_eq!(
+            pick_last_used(None, vec![prefs_mtime, indexed_atime, None]),
+            indexed_atime
+        );
+        assert_eq!(pick_last_used(None, vec![None]), None);
+    }
+}
@@ -0,0 +1,329 @@
+//! Parse and compare macOS app versions.
+//!
+//! `CFBundleShortVersionString` and `CFBundleVersion` are free-form strings.
+//! In practice they look like `1.2.3`, `16.0 (16A242d)`, `2024.3.1-beta`,
+//! `1.0b3` or `v2.1+45`. [`AppVersion`] splits them into numeric release
+//! components, an optional pre-release tag and an optional build identifier
+//! so they can be ordered:
+//!
+//! - release components compare numerically, missing ones count as zero
+//!   (`1.2 == 1.2.0`),
+//! - a pre-release sorts before the release (`2.0-beta < 2.0`), ranked
+//!   alpha < beta < rc,
+//! - the build identifier breaks remaining ties, comparing digit runs
+//!   numerically (`16A242 < 16A2421`); a missing build sorts lowest.
+//!   Apple builds (`16A242d`) whose number is 5000 or more are beta seeds
+//!   and sort before the release of the same train (`16A5171c < 16A242d`).
+
+use std::{cmp::Ordering, fmt};
+
+#[derive(Clone, Debug, Default)]
+pub struct AppVersion {
+    pub release: Vec<u64>,
+    pub pre_release: Option<PreRelease>,
+    pub build: Option<String>,
+    raw: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PreRelease {
+    /// 0 = alpha, 1 = beta (and unknown tags), 2 = release candidate.
+    pub rank: u8,
+    pub label: String,
+    pub number: Vec<u64>,
+}
+
+fn pre_release_rank(label: &str) -> u8 {
+    match label {
+        "a" | "alpha" | "dev" | "d" | "nightly" | "canary" => 0,
+        "rc" | "pre" | "preview" | "c" => 2,
+        _ => 1,
+    }
+}
+
+fn leading_number(s: &str) -> Option<u64> {
+    let digits: String = s.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+fn parse_pre_release(s: &str) -> Option<PreRelease> {
+    let s = s
+        .trim_matches(|c: char| c == '-' || c == '.' || c == ' ' || c == '_')
+        .to_lowercase();
+    if s.is_empty() {
+        return None;
+    }
+    let label: String = s.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+    let number = s[label.len()..]
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|p| p.parse().ok())
+        .collect();
+    Some(PreRelease {
+        rank: pre_release_rank(&label),
+        label,
+        number,
+    })
+}
+
+impl AppVersion {
+    pub fn parse(raw: &str) -> Self {
+        let raw = raw.trim();
+        let mut rest = raw;
+        let mut build = None;
+
+        // "16.0 (16A242d)"
+        if let (Some(open), true) = (rest.find('('), rest.ends_with(')')) {
+            let inner = rest[open + 1..rest.len() - 1].trim();
+            if !inner.is_empty() {
+                build = Some(inner.to_string());
+            }
+            rest = rest[..open].trim();
+        }
+        // "2.1+45" (semver build metadata)
+        if let Some((main, meta)) = rest.split_once('+') {
+            if build.is_none() && !meta.is_empty() {
+                build = Some(meta.to_string());
+            }
+            rest = main;
+        }
+        let rest = rest
+            .strip_prefix('v')
+            .or_else(|| rest.strip_prefix('V'))
+            .unwrap_or(rest);
+
+        // The release part is the leading run of digits and dots.
+        let split = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let (main, tail) = rest.split_at(split);
+        let release: Vec<u64> = main
+            .split('.')
+            .filter(|p| !p.is_empty())
+            .filter_map(leading_number)
+            .collect();
+
+        Self {
+            release,
+            pre_release: parse_pre_release(tail),
+            build,
+            raw: raw.to_string(),
+        }
+    }
+
+    /// Combine `CFBundleShortVersionString` and `CFBundleVersion`. The
+    /// bundle version is the build identifier unless it merely repeats the
+    /// short version.
+    pub fn from_bundle(short: Option<&str>, build: Option<&str>) -> Option<Self> {
+        match (short, build) {
+            (Some(s), b) => {
+                let mut v = Self::parse(s);
+                if let Some(b) = b.map(str::trim).filter(|b| !b.is_empty() && *b != s.trim()) {
+                    v.build = Some(b.to_string());
+                }
+                Some(v)
+            }
+            (None, Some(b)) => Some(Self::parse(b)),
+            (None, None) => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+}
+
+impl fmt::Display for AppVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+fn cmp_release(a: &[u64], b: &[u64]) -> Ordering {
+    let n = a.len().max(b.len());
+    (0..n)
+        .map(|i| a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0)))
+        .find(|o| *o != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+fn cmp_pre_release(a: &Option<PreRelease>, b: &Option<PreRelease>) -> Ordering {
+    match (a, b) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => a
+            .rank
+            .cmp(&b.rank)
+            .then_with(|| cmp_release(&a.number, &b.number)),
+    }
+}
+
+/// Apple seeds number their builds from here within a train (`16A5171c`);
+/// releases stay below (`16A242d`).
+const APPLE_SEED_BUILD: u64 = 5000;
+
+/// An Apple build `<major><train letter><number><suffix>`, e.g. `16A242d`.
+fn apple_build(s: &str) -> Option<(u64, char, u64, &str)> {
+    let train_at = s.find(|c: char| !c.is_ascii_digit())?;
+    let (major, rest) = s.split_at(train_at);
+    let train = rest.chars().next().filter(char::is_ascii_uppercase)?;
+    let rest = &rest[1..];
+    let num_end = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let (number, suffix) = rest.split_at(num_end);
+    if !suffix.chars().all(|c| c.is_ascii_lowercase()) || suffix.len() > 1 {
+        return None;
+    }
+    Some((major.parse().ok()?, train, number.parse().ok()?, suffix))
+}
+
+/// Compare two build identifiers, treating runs of digits as numbers. Two
+/// Apple builds of the same train rank a seed below the release.
+fn cmp_build(a: &str, b: &str) -> Ordering {
+    if let (Some(x), Some(y)) = (apple_build(a), apple_build(b)) {
+        let release = |n: u64| n < APPLE_SEED_BUILD;
+        return x
+            .0
+            .cmp(&y.0)
+            .then(x.1.cmp(&y.1))
+            .then(release(x.2).cmp(&release(y.2)))
+            .then(x.2.cmp(&y.2))
+            .then(x.3.cmp(y.3));
+    }
+
+    fn runs(s: &str) -> Vec<Result<u64, String>> {
+        let mut out = Vec::new();
+        let mut cur = String::new();
+        let mut digit = None;
+        for c in s.chars() {
+            let d = c.is_ascii_digit();
+            if digit.is_some_and(|prev| prev != d) {
+                out.push(run(&cur, digit == Some(true)));
+                cur.clear();
+            }
+            digit = Some(d);
+            cur.push(c);
+        }
+        if !cur.is_empty() {
+            out.push(run(&cur, digit == Some(true)));
+        }
+        out
+    }
+    fn run(s: &str, digits: bool) -> Result<u64, String> {
+        if digits {
+            s.parse().map_err(|_| s.to_string())
+        } else {
+            Err(s.to_lowercase())
+        }
+    }
+    let (a, b) = (runs(a), runs(b));
+    for (x, y) in a.iter().zip(b.iter()) {
+        let o = match (x, y) {
+            (Ok(x), Ok(y)) => x.cmp(y),
+            (Err(x), Err(y)) => x.cmp(y),
+            // Numbers sort before letters.
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+        };
+        if o != Ordering::Equal {
+            return o;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+impl Ord for AppVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp_release(&self.release, &other.release)
+            .then_with(|| cmp_pre_release(&self.pre_release, &other.pre_release))
+            .then_with(|| match (&self.build, &other.build) {
+                (Some(a), Some(b)) => cmp_build(a, b),
+                // Keep the order total: a known build sorts above none.
+                (a, b) => a.is_some().cmp(&b.is_some()),
+            })
+    }
+}
+
+impl PartialOrd for AppVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for AppVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for AppVersion {}
+
+/// Compare two optional `(short version, build)` pairs. Missing versions
+/// sort lowest.
+pub fn compare_bundle_versions(
+    a: (Option<&str>, Option<&str>),
+    b: (Option<&str>, Option<&str>),
+) -> Ordering {
+    AppVersion::from_bundle(a.0, a.1).cmp(&AppVersion::from_bundle(b.0, b.1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> AppVersion {
+        AppVersion::parse(s)
+    }
+
+    #[test]
+    fn parses_common_shapes() {
+        let xcode = v("16.0 (16A242d)");
+        assert_eq!(xcode.release, vec![16, 0]);
+        assert_eq!(xcode.build.as_deref(), Some("16A242d"));
+        assert!(xcode.pre_release.is_none());
+
+        let beta = v("2024.3.1-beta");
+        assert_eq!(beta.release, vec![2024, 3, 1]);
+        assert_eq!(beta.pre_release.as_ref().unwrap().label, "beta");
+
+        let b3 = v("1.0b3");
+        assert_eq!(b3.release, vec![1, 0]);
+        assert_eq!(b3.pre_release.as_ref().unwrap().number, vec![3]);
+
+        let semver = v("v2.1+45");
+        assert_eq!(semver.release, vec![2, 1]);
+        assert_eq!(semver.build.as_deref(), Some("45"));
+    }
+
+    #[test]
+    fn orders_versions() {
+        assert!(v("1.10") > v("1.9"));
+        assert_eq!(v("1.2"), v("1.2.0"));
+        assert!(v("2.0-beta") < v("2.0"));
+        assert!(v("2.0-alpha") < v("2.0-beta.2"));
+        assert!(v("2.0-beta.2") < v("2.0-rc1"));
+        assert!(v("16.0 (16A5171c)") < v("16.0 (16A242d)"));
+        assert!(v("16.0 (16A5163d)") < v("16.0 (16A5171c)"));
+        assert!(v("16.0 (16A242d)") < v("16.0 (16B40)"));
+        assert!(v("15.4 (15F31d)") < v("16.0 (16A242d)"));
+    }
+
+    #[test]
+    fn combines_short_version_and_build() {
+        let a = AppVersion::from_bundle(Some("3.1"), Some("3101")).unwrap();
+        let b = AppVersion::from_bundle(Some("3.1"), Some("3105")).unwrap();
+        assert!(a < b);
+        assert_eq!(
+            AppVersion::from_bundle(Some("1.0"), Some("1.0"))
+                .unwrap()
+                .build,
+            None
+        );
+        assert!(AppVersion::from_bundle(None, Some("42")).unwrap() > v("41"));
+        assert_eq!(
+            compare_bundle_versions((None, None), (Some("0.1"), None)),
+            Ordering::Less
+        );
+    }
+}
@@ -1,9 +1,11 @@
 //! App Uninstaller backend (Tauri).
 //!
 //! `core` holds the pure filesystem/process logic; `commands` exposes it
-//! to the React frontend through Tauri commands. `progress` defines the
-//! single typed event channel used to stream task progress to the UI.
+//! to the React frontend through Tauri commands. `progress` forwards
+//! `core` task progress to the UI as a single typed event channel.
+//! `cli` runs a few `core` operations headless for scripted use.
 
+pub mod cli;
 pub mod commands;
 pub mod core;
 pub mod models;
@@ -25,11 +27,31 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             commands::list_apps,
+            commands::list_intel_only_apps,
+            commands::list_unused_apps,
+            commands::find_duplicate_apps,
+            commands::check_for_updates,
             commands::find_related,
             commands::is_app_running,
             commands::kill_app,
             commands::get_app_size,
+            commands::analyze_storage,
+            commands::get_app_security,
+            commands::export_inventory,
             commands::uninstall,
+            commands::preview_clean_app_data,
+            commands::clean_app_data,
+            commands::reset_app,
+            commands::restore_backup,
+            commands::preview_migrate_app_data,
+            commands::migrate_app_data,
+            commands::apply_profile,
+            commands::get_exclusions,
+            commands::set_exclusions,
+            commands::get_settings,
+            commands::update_settings,
+            commands::thin_app,
+            commands::check_access,
             commands::reveal_in_finder,
         ])
         .run(tauri::generate_context!())
@@ -5,17 +5,141 @@
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AppInfo {
     pub path: PathBuf,
     pub name: String,
     pub bundle_id: Option<String>,
     pub version: Option<String>,
+    /// `CFBundleVersion` (build number). Parse both with
+    /// `core::version::AppVersion::from_bundle` to compare versions.
+    pub build_version: Option<String>,
     pub executable: Option<String>,
     /// Last-modified time as a Unix timestamp (seconds). `None` if unreadable.
     pub modified_at: Option<i64>,
+    /// Best-effort last-launch time as a Unix timestamp (seconds); see
+    /// `core::usage`. `None` if no usage signal was readable.
+    pub last_used_at: Option<i64>,
     pub running: bool,
+    pub protection: AppProtection,
+    /// Slices of the main executable. Empty when it is missing or not a
+    /// Mach-O.
+    pub architectures: Vec<Architecture>,
+    /// Homebrew cask token when the bundle was installed with
+    /// `brew install --cask`; see `core::casks`.
+    pub cask: Option<String>,
+    pub install_source: InstallSource,
+}
+
+/// How an app got onto the machine. Inferred by `core::source`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InstallSource {
+    /// Bundle carries a `_MASReceipt`; can be redownloaded from the store.
+    AppStore,
+    Homebrew,
+    Setapp,
+    /// Listed in an installer package receipt.
+    Pkg,
+    DragAndDrop,
+    #[default]
+    Unknown,
+}
+
+/// CPU architecture of a Mach-O slice.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Architecture {
+    #[serde(rename = "x86_64")]
+    X86_64,
+    #[serde(rename = "arm64")]
+    Arm64,
+    #[serde(rename = "arm64e")]
+    Arm64e,
+    #[serde(rename = "i386")]
+    I386,
+    #[serde(rename = "ppc")]
+    PowerPc,
+    #[serde(rename = "other")]
+    Other,
+}
+
+impl Architecture {
+    pub fn is_arm64(self) -> bool {
+        matches!(self, Architecture::Arm64 | Architecture::Arm64e)
+    }
+}
+
+/// Whether an app is one of Apple's own and must not be uninstalled without
+/// an explicit override.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AppProtection {
+    /// Third-party app; no restrictions.
+    #[default]
+    None,
+    /// Apple-signed `com.apple.*` app outside the system volume (e.g.
+    /// Pages, Xcode). Removable with an override.
+    Apple,
+    /// Lives on the sealed system volume; SIP makes it unremovable.
+    SealedSystem,
+}
+
+/// Apps that look like copies or channels of the same app. Produced by
+/// `core::duplicates`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    /// Shared bundle id, or the normalized name when there is none.
+    pub key: String,
+    pub apps: Vec<DuplicateEntry>,
+    /// Total size of every entry except the newest.
+    pub redundant_bytes: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateEntry {
+    pub app: AppInfo,
+    pub newest: bool,
+    /// Bundle size; only computed for redundant copies.
+    pub size: Option<u64>,
+}
+
+/// Who signed an app, as decoded from its embedded code signature. See
+/// `core::codesign`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppSecurityInfo {
+    pub signed: bool,
+    pub signer: SignerKind,
+    /// Signing identifier, usually the bundle id.
+    pub identifier: Option<String>,
+    pub team_id: Option<String>,
+    /// Certificate common names, leaf first.
+    pub authorities: Vec<String>,
+    /// Raw `CS_*` code directory flags.
+    pub flags: u32,
+    pub hardened_runtime: bool,
+    /// Embedded entitlements plist, converted to JSON.
+    pub entitlements: Option<serde_json::Value>,
+    /// Files sealed by `_CodeSignature/CodeResources`.
+    pub sealed_resources: Option<usize>,
+    /// A notarization ticket is stapled to the bundle.
+    pub notarization_stapled: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SignerKind {
+    /// No signature, or one whose leaf authority we don't recognise.
+    #[default]
+    Unknown,
+    AdHoc,
+    Apple,
+    AppStore,
+    DeveloperId,
+    Development,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -25,6 +149,30 @@ pub struct UninstallReport {
     pub removed: Vec<PathBuf>,
     pub failed: Vec<UninstallFailure>,
     pub aborted: bool,
+    /// Archive of everything removed, when a backup was taken first. See
+    /// `core::backup`.
+    #[serde(default)]
+    pub backup: Option<PathBuf>,
+}
+
+/// Result of `thin_app`: which fat binaries were rewritten and how much
+/// space that freed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThinReport {
+    pub app_path: PathBuf,
+    pub architecture: Architecture,
+    pub thinned: Vec<ThinnedFile>,
+    pub failed: Vec<UninstallFailure>,
+    pub bytes_saved: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThinnedFile {
+    pub path: PathBuf,
+    pub original_size: u64,
+    pub new_size: u64,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -32,4 +180,322 @@ pub struct UninstallReport {
 pub struct UninstallFailure {
     pub path: PathBuf,
     pub error: String,
+    pub kind: FailureKind,
+}
+
+/// Distinguishes "we tried and the filesystem said no" from "the removal
+/// policy refused to touch this path".
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum FailureKind {
+    Error,
+    Refused { reason: String },
+}
+
+/// Result of checking one app's Sparkle appcast. `error` is set when the
+/// feed could not be fetched or parsed; the version fields are then empty.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateCheck {
+    pub app_path: PathBuf,
+    pub name: String,
+    pub feed_url: String,
+    pub installed_version: Option<String>,
+    pub latest_version: Option<String>,
+    pub latest_build: Option<String>,
+    pub minimum_system_version: Option<String>,
+    pub download_url: Option<String>,
+    pub release_notes_url: Option<String>,
+    pub update_available: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Markdown,
+}
+
+/// One app in an `export_inventory` file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryEntry {
+    pub name: String,
+    pub bundle_id: Option<String>,
+    pub version: Option<String>,
+    pub path: PathBuf,
+    pub size: Option<u64>,
+    pub running: bool,
+    pub install_source: InstallSource,
+    /// Only filled when the export asked for related paths.
+    pub related: Option<Vec<InventoryItem>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryItem {
+    pub path: PathBuf,
+    pub size: Option<u64>,
+}
+
+/// Result of `apply_profile`. With `dry_run` nothing is removed and every
+/// `report` is `None`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileReport {
+    pub profile_name: Option<String>,
+    pub dry_run: bool,
+    pub apps: Vec<ProfileAppResult>,
+    /// Profile selectors that matched no installed app.
+    pub unmatched: Vec<String>,
+    pub extra_removed: Vec<PathBuf>,
+    pub extra_failed: Vec<UninstallFailure>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileAppResult {
+    pub app_path: PathBuf,
+    pub name: String,
+    pub related: Vec<PathBuf>,
+    /// Related paths left alone because of the profile's `keep` list.
+    pub kept: Vec<PathBuf>,
+    pub killed: u32,
+    pub report: Option<UninstallReport>,
+    pub error: Option<String>,
+}
+
+/// User exclusion list, persisted as `exclusions.json` in the app config
+/// directory. Entries may start with `~/`. See `core::exclusions`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExclusionRules {
+    pub paths: Vec<String>,
+    pub globs: Vec<String>,
+    /// Glob patterns such as `com.microsoft.*`, matched against file names.
+    pub bundle_ids: Vec<String>,
+}
+
+/// Backend settings, persisted as `settings.json` in the app config
+/// directory. Defaults and schema migrations live in `core::settings`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    pub schema_version: u32,
+    /// Folders scanned for `.app` bundles. Entries may start with `~/`.
+    pub scan_roots: Vec<String>,
+    pub removal_mode: RemovalMode,
+    pub kill_policy: KillPolicy,
+    /// How long `kill_app` waits for killed processes to disappear.
+    pub kill_deadline_ms: u64,
+    /// How deep `find_related_paths` walks each Library folder.
+    pub related_max_depth: usize,
+    /// Pre-flight every path and roll back on a mid-run failure instead of
+    /// leaving a partial uninstall. See `core::transaction`.
+    pub transactional_uninstall: bool,
+    /// Archive related files before `uninstall` removes them.
+    pub backup_before_uninstall: bool,
+    /// Where backups go. Empty means `backups` in the app data folder; may
+    /// start with `~/`.
+    pub backups_dir: String,
+    pub backup_format: BackupFormat,
+}
+
+/// What "remove" means for bundles and related files.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RemovalMode {
+    /// Move to the Trash; fail if the Trash is unavailable.
+    Trash,
+    /// Move to the Trash, deleting permanently if that fails.
+    #[default]
+    TrashOrDelete,
+    /// Delete permanently without going through the Trash.
+    Delete,
+}
+
+/// What `uninstall` does when the app is running.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum KillPolicy {
+    /// Abort the uninstall.
+    #[default]
+    Refuse,
+    /// Force-quit the app first.
+    Kill,
+}
+
+/// Whether the app holds Full Disk Access, as far as `check_access` can
+/// tell from the TCC-protected folders it probes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FullDiskAccess {
+    Granted,
+    Missing,
+    /// None of the probed folders exist.
+    Unknown,
+}
+
+/// Read/write probe of one location.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocationAccess {
+    pub path: PathBuf,
+    pub exists: bool,
+    pub readable: bool,
+    pub writable: bool,
+    /// Why a probe failed, when it did.
+    pub error: Option<String>,
+}
+
+/// Result of `check_access`: Full Disk Access status plus a probe of each
+/// location the scan and uninstall touch.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessReport {
+    pub full_disk_access: FullDiskAccess,
+    pub locations: Vec<LocationAccess>,
+}
+
+/// One top-level entry of a `~/Library` folder and its size on disk.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageEntry {
+    pub path: PathBuf,
+    /// Library folder the entry sits in, e.g. `Caches`.
+    pub category: String,
+    pub bytes: u64,
+}
+
+/// Data an installed app keeps outside its bundle.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppStorage {
+    pub app_path: PathBuf,
+    pub name: String,
+    pub bundle_id: Option<String>,
+    pub bytes: u64,
+    /// Largest first.
+    pub entries: Vec<StorageEntry>,
+}
+
+/// Result of `analyze_storage`, apps ranked by bytes, largest first.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageReport {
+    pub total_bytes: u64,
+    pub apps: Vec<AppStorage>,
+    /// Entries no installed app claims (often leftovers of removed apps),
+    /// largest first.
+    pub unattributed: Vec<StorageEntry>,
+}
+
+/// Kinds of app data `clean_app_data` may remove. All of them are
+/// regenerated by the app; preferences and Application Support never are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CleanCategory {
+    Caches,
+    Logs,
+    HttpStorages,
+    SavedApplicationState,
+    DiagnosticReports,
+}
+
+/// One path `clean_app_data` would remove, sized beforehand.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanItem {
+    pub path: PathBuf,
+    pub category: CleanCategory,
+    pub bytes: u64,
+}
+
+/// Result of `clean_app_data`, shaped like [`UninstallReport`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanReport {
+    pub app_path: PathBuf,
+    pub removed: Vec<PathBuf>,
+    pub failed: Vec<UninstallFailure>,
+    /// Sum of the pre-computed sizes of the removed items.
+    pub freed_bytes: u64,
+}
+
+/// One archived path and where it goes back to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupEntry {
+    pub original: PathBuf,
+    /// Path inside the archive.
+    pub archived: String,
+}
+
+/// `manifest.json` at the root of every backup archive.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    /// Unix timestamp (seconds).
+    pub created_at: i64,
+    pub label: String,
+    /// The app the backed-up files belonged to, as scanned at backup time.
+    #[serde(default)]
+    pub app: Option<AppInfo>,
+    pub entries: Vec<BackupEntry>,
+}
+
+/// Archive format for backups.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BackupFormat {
+    /// `.tar.zst`: smaller, keeps permissions and symlinks exactly.
+    #[default]
+    TarZst,
+    /// `.zip`: opens anywhere, including Finder.
+    Zip,
+}
+
+/// Result of `restore_backup`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreReport {
+    pub archive: PathBuf,
+    pub restored: Vec<PathBuf>,
+    /// Includes entries skipped because something already exists at the
+    /// original location, as `Refused`.
+    pub failed: Vec<UninstallFailure>,
+}
+
+/// Result of `reset_app`: the removal report plus how many processes were
+/// killed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetReport {
+    #[serde(flatten)]
+    pub report: UninstallReport,
+    pub killed: u32,
+}
+
+/// One rename `migrate_app_data` performs, from the old bundle id's
+/// location to the new one's.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationMove {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    /// Why the move will be skipped, e.g. `to` already exists.
+    pub blocked: Option<String>,
+}
+
+/// Result of `migrate_app_data`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationReport {
+    pub from_bundle_id: String,
+    pub to_bundle_id: String,
+    pub moved: Vec<MigrationMove>,
+    /// Blocked moves, as `Refused` at their source path.
+    pub failed: Vec<UninstallFailure>,
 }
@@ -1,33 +1,24 @@
-//! Typed progress events emitted to the frontend over the `progress` channel.
+//! Forward `core` task progress to the frontend over the `progress` channel.
 
-use serde::Serialize;
 use tauri::{AppHandle, Emitter};
 
+use crate::core::{ProgressEvent, ProgressSink};
+
 pub const EVENT_NAME: &str = "progress";
 
-#[derive(Clone, Debug, Serialize)]
-#[serde(tag = "kind", rename_all = "snake_case")]
-pub enum ProgressEvent {
-    RefreshApps {
-        progress: f32,
-        message: String,
-        finished: bool,
-        error: Option<String>,
-    },
-    FindRelated {
-        progress: f32,
-        message: String,
-        finished: bool,
-        error: Option<String>,
-    },
-    Uninstall {
-        progress: f32,
-        message: String,
-        finished: bool,
-        error: Option<String>,
-    },
+/// [`ProgressSink`] that emits every event to the webview.
+pub struct TauriSink {
+    app: AppHandle,
+}
+
+impl TauriSink {
+    pub fn new(app: &AppHandle) -> Self {
+        Self { app: app.clone() }
+    }
 }
 
-pub fn emit(app: &AppHandle, event: ProgressEvent) {
-    let _ = app.emit(EVENT_NAME, event);
+impl ProgressSink for TauriSink {
+    fn emit(&self, event: ProgressEvent) {
+        let _ = self.app.emit(EVENT_NAME, event);
+    }
 }
@@ -1,6 +1,7 @@
 import { useEffect } from "react";
 import { TooltipProvider } from "@/components/ui/tooltip";
 import { Toaster } from "@/components/ui/sonner";
+import { toast } from "sonner";
 import { Header } from "@/components/header";
 import { AppsSidebar } from "@/components/apps-sidebar";
 import { DetailPanel } from "@/components/detail-panel";
@@ -10,6 +11,7 @@ import {
   useTaskStore,
 } from "@/stores/uninstaller";
 import { tauriListen } from "@/lib/tauri";
+import { uninstallerApi } from "@/lib/api/uninstaller";
 import { IDS } from "@/lib/styles";
 import type { AppInfo, ProgressEvent } from "@/types/models";
 
@@ -20,8 +22,22 @@ export default function App() {
   const setProgress = useTaskStore((s) => s.setProgress);
   const loading = useAppsStore((s) => s.loading);
 
+  // Without Full Disk Access, TCC hides other apps' containers and the
+  // related-file list comes back short. Say so before the first scan.
   useEffect(() => {
-    fetchApps();
+    uninstallerApi
+      .checkAccess()
+      .then((report) => {
+        if (report.fullDiskAccess === "missing") {
+          toast.warning("Full Disk Access is off", {
+            description:
+              "Some app data can't be read, so related files may be missed. Grant access in System Settings → Privacy & Security → Full Disk Access, then refresh.",
+            duration: Infinity,
+          });
+        }
+      })
+      .catch(() => {})
+      .finally(() => fetchApps());
   }, [fetchApps]);
 
   // Refresh whenever the user returns to the window. Skips while a refresh is
@@ -45,7 +61,7 @@ export default function App() {
 
   function handleSelect(app: AppInfo) {
     select(app.path);
-    fetchRelated(app.name, app.bundleId);
+    fetchRelated(app.name, app.bundleId, app.cask);
   }
 
   return (
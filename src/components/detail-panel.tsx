@@ -35,11 +35,13 @@ import {
   Trash2Icon,
 } from "lucide-react";
 import {
+  selectBusy,
   useAppsStore,
   useRelatedStore,
   useTaskStore,
 } from "@/stores/uninstaller";
 import { uninstallerApi } from "@/lib/api/uninstaller";
+import { errorMessage, isCommandError } from "@/lib/tauri";
 import { UninstallConfirm } from "@/components/uninstall-confirm";
 import { toast } from "sonner";
 import { IDS, STYLES } from "@/lib/styles";
@@ -53,8 +55,7 @@ export function DetailPanel() {
   const fetchApps = useAppsStore((s) => s.fetchApps);
 
   const related = useRelatedStore();
-  const taskRunning =
-    useTaskStore((s) => s.current && !s.current.finished) ?? false;
+  const taskRunning = useTaskStore(selectBusy);
 
   const app = apps.find((a) => a.path === selectedPath) ?? null;
 
@@ -103,7 +104,13 @@ export function DetailPanel() {
       related.clear();
       await fetchApps();
     } catch (e) {
-      toast.error("Uninstall failed", { description: String(e) });
+      if (isCommandError(e) && e.code === "appRunning") {
+        toast.error(`${app.name} is running`, {
+          description: "Quit it first, then uninstall.",
+        });
+      } else {
+        toast.error("Uninstall failed", { description: errorMessage(e) });
+      }
     }
   }
 
@@ -125,7 +132,7 @@ export function DetailPanel() {
       }
       await fetchApps();
     } catch (e) {
-      toast.error("Quit failed", { description: String(e) });
+      toast.error("Quit failed", { description: errorMessage(e) });
     }
   }
 
@@ -141,7 +148,7 @@ export function DetailPanel() {
         uninstallDisabled={uninstallDisabled}
         rescanDisabled={related.loading || taskRunning}
         onReveal={() => uninstallerApi.revealInFinder(app.path)}
-        onRescan={() => related.fetchRelated(app.name, app.bundleId)}
+        onRescan={() => related.fetchRelated(app.name, app.bundleId, app.cask)}
         onUninstall={() => setConfirmOpen(true)}
         onQuit={() => setQuitOpen(true)}
       />
@@ -1,11 +1,47 @@
 import { tauriInvoke } from "@/lib/tauri";
-import type { AppInfo, UninstallReport } from "@/types/models";
+import type {
+  AccessReport,
+  AppInfo,
+  AppSecurityInfo,
+  Architecture,
+  CleanCategory,
+  CleanItem,
+  CleanReport,
+  DuplicateGroup,
+  ExclusionRules,
+  ExportFormat,
+  InstallSource,
+  MigrationMove,
+  MigrationReport,
+  ProfileReport,
+  ResetReport,
+  RestoreReport,
+  Settings,
+  StorageReport,
+  ThinReport,
+  UninstallReport,
+  UpdateCheck,
+} from "@/types/models";
 
 export const uninstallerApi = {
-  listApps: () => tauriInvoke<AppInfo[]>("list_apps"),
+  listApps: (source: InstallSource | null = null) =>
+    tauriInvoke<AppInfo[]>("list_apps", { source }),
 
-  findRelated: (appName: string, bundleId: string | null) =>
-    tauriInvoke<string[]>("find_related", { bundleId, appName }),
+  listIntelOnlyApps: () => tauriInvoke<AppInfo[]>("list_intel_only_apps"),
+
+  listUnusedApps: (olderThanDays: number) =>
+    tauriInvoke<AppInfo[]>("list_unused_apps", { olderThanDays }),
+
+  findDuplicateApps: () =>
+    tauriInvoke<DuplicateGroup[]>("find_duplicate_apps"),
+
+  checkForUpdates: () => tauriInvoke<UpdateCheck[]>("check_for_updates"),
+
+  findRelated: (
+    appName: string,
+    bundleId: string | null,
+    cask: string | null = null,
+  ) => tauriInvoke<string[]>("find_related", { bundleId, appName, cask }),
 
   isAppRunning: (
     appPath: string | null,
@@ -19,19 +55,112 @@ export const uninstallerApi = {
   getAppSize: (path: string) =>
     tauriInvoke<number | null>("get_app_size", { path }),
 
+  getAppSecurity: (path: string) =>
+    tauriInvoke<AppSecurityInfo>("get_app_security", { path }),
+
   uninstall: (
     appPath: string,
     appName: string,
     bundleId: string | null,
     relatedPaths: string[],
+    allowProtected = false,
+    viaBrew = false,
   ) =>
     tauriInvoke<UninstallReport>("uninstall", {
       appPath,
       appName,
       bundleId,
       relatedPaths,
+      allowProtected,
+      viaBrew,
     }),
 
+  previewCleanAppData: (
+    appPath: string,
+    appName: string,
+    categories: CleanCategory[] = [],
+  ) =>
+    tauriInvoke<CleanItem[]>("preview_clean_app_data", {
+      appPath,
+      appName,
+      categories,
+    }),
+
+  cleanAppData: (
+    appPath: string,
+    appName: string,
+    categories: CleanCategory[] = [],
+  ) =>
+    tauriInvoke<CleanReport>("clean_app_data", {
+      appPath,
+      appName,
+      categories,
+    }),
+
+  resetApp: (
+    appPath: string,
+    appName: string,
+    bundleId: string | null,
+    backup = false,
+  ) =>
+    tauriInvoke<ResetReport>("reset_app", {
+      appPath,
+      appName,
+      bundleId,
+      backup,
+    }),
+
+  restoreBackup: (archive: string) =>
+    tauriInvoke<RestoreReport>("restore_backup", { archive }),
+
+  previewMigrateAppData: (fromBundleId: string, toBundleId: string) =>
+    tauriInvoke<MigrationMove[]>("preview_migrate_app_data", {
+      fromBundleId,
+      toBundleId,
+    }),
+
+  migrateAppData: (fromBundleId: string, toBundleId: string) =>
+    tauriInvoke<MigrationReport>("migrate_app_data", {
+      fromBundleId,
+      toBundleId,
+    }),
+
+  applyProfile: (path: string, dryRun = false) =>
+    tauriInvoke<ProfileReport>("apply_profile", { path, dryRun }),
+
+  getExclusions: () => tauriInvoke<ExclusionRules>("get_exclusions"),
+
+  setExclusions: (rules: ExclusionRules) =>
+    tauriInvoke<ExclusionRules>("set_exclusions", { rules }),
+
+  getSettings: () => tauriInvoke<Settings>("get_settings"),
+
+  updateSettings: (settings: Settings) =>
+    tauriInvoke<Settings>("update_settings", { settings }),
+
+  exportInventory: (
+    format: ExportFormat,
+    path: string,
+    includeRelated = false,
+  ) =>
+    tauriInvoke<number>("export_inventory", { format, path, includeRelated }),
+
+  thinApp: (
+    appPath: string,
+    architecture: Architecture,
+    acceptInvalidSignature = false,
+  ) =>
+    tauriInvoke<ThinReport>("thin_app", {
+      appPath,
+      architecture,
+      acceptInvalidSignature,
+    }),
+
+  analyzeStorage: () => tauriInvoke<StorageReport>("analyze_storage"),
+
+  checkAccess: (paths: string[] = []) =>
+    tauriInvoke<AccessReport>("check_access", { paths }),
+
   revealInFinder: (path: string) =>
     tauriInvoke<void>("reveal_in_finder", { path }),
 };
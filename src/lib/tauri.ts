@@ -1,5 +1,6 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type { CommandError } from "@/types/models";
 
 function isInsideTauri(): boolean {
   return typeof window !== "undefined" && "__TAURI_INTERNALS__" in window;
@@ -22,6 +23,22 @@ export async function tauriInvoke<T>(
   return invoke<T>(cmd, args);
 }
 
+export function isCommandError(e: unknown): e is CommandError {
+  return (
+    typeof e === "object" &&
+    e !== null &&
+    "code" in e &&
+    "message" in e
+  );
+}
+
+/** Human-readable text for anything a command (or the IPC layer) threw. */
+export function errorMessage(e: unknown): string {
+  if (isCommandError(e)) return e.message;
+  if (e instanceof Error) return e.message;
+  return String(e);
+}
+
 export async function tauriListen<T>(
   event: string,
   handler: (payload: T) => void,
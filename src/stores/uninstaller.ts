@@ -1,6 +1,7 @@
 import { create } from "zustand";
 import type { AppInfo, ProgressEvent } from "@/types/models";
 import { uninstallerApi } from "@/lib/api/uninstaller";
+import { errorMessage } from "@/lib/tauri";
 
 interface AppsState {
   apps: AppInfo[];
@@ -24,7 +25,7 @@ export const useAppsStore = create<AppsState>((set) => ({
       const apps = await uninstallerApi.listApps();
       set({ apps, loading: false });
     } catch (e) {
-      set({ error: String(e), loading: false });
+      set({ error: errorMessage(e), loading: false });
     }
   },
 
@@ -37,7 +38,11 @@ interface RelatedState {
   loading: boolean;
   error: string | null;
 
-  fetchRelated: (appName: string, bundleId: string | null) => Promise<void>;
+  fetchRelated: (
+    appName: string,
+    bundleId: string | null,
+    cask?: string | null,
+  ) => Promise<void>;
   toggle: (path: string) => void;
   toggleAll: (checked: boolean) => void;
   clear: () => void;
@@ -49,13 +54,13 @@ export const useRelatedStore = create<RelatedState>((set, get) => ({
   loading: false,
   error: null,
 
-  fetchRelated: async (appName, bundleId) => {
+  fetchRelated: async (appName, bundleId, cask = null) => {
     set({ loading: true, error: null, paths: [], selected: new Set() });
     try {
-      const paths = await uninstallerApi.findRelated(appName, bundleId);
+      const paths = await uninstallerApi.findRelated(appName, bundleId, cask);
       set({ paths, selected: new Set(paths), loading: false });
     } catch (e) {
-      set({ error: String(e), loading: false });
+      set({ error: errorMessage(e), loading: false });
     }
   },
 
@@ -73,12 +78,50 @@ export const useRelatedStore = create<RelatedState>((set, get) => ({
   clear: () => set({ paths: [], selected: new Set(), error: null }),
 }));
 
+/** How many dropped tasks `finishedSeq` remembers. */
+const FINISHED_SEQ_LIMIT = 64;
+
 interface TaskState {
+  /** Latest event per task id. Finished tasks are dropped once another
+   * event arrives. */
+  tasks: Record<number, ProgressEvent>;
+  /** Final seq of recently dropped finished tasks, so an event of theirs
+   * that arrives late can't bring them back as running. */
+  finishedSeq: Record<number, number>;
+  /** Most recent event across all tasks. */
   current: ProgressEvent | null;
   setProgress: (event: ProgressEvent) => void;
 }
 
 export const useTaskStore = create<TaskState>((set) => ({
+  tasks: {},
+  finishedSeq: {},
   current: null,
-  setProgress: (event) => set({ current: event }),
+  setProgress: (event) =>
+    set((s) => {
+      const prev = s.tasks[event.taskId];
+      if (prev && prev.seq >= event.seq) return s;
+      const last = s.finishedSeq[event.taskId];
+      if (last !== undefined && event.seq <= last) return s;
+      const tasks: Record<number, ProgressEvent> = {};
+      const finishedSeq = { ...s.finishedSeq };
+      for (const t of Object.values(s.tasks)) {
+        if (!t.finished) tasks[t.taskId] = t;
+        else if (t.taskId !== event.taskId) finishedSeq[t.taskId] = t.seq;
+      }
+      // Task ids only grow, and integer keys iterate in ascending order.
+      const ids = Object.keys(finishedSeq);
+      for (const id of ids.slice(0, ids.length - FINISHED_SEQ_LIMIT)) {
+        delete finishedSeq[Number(id)];
+      }
+      tasks[event.taskId] = event;
+      return { tasks, finishedSeq, current: event };
+    }),
 }));
+
+/** True while a task other than a background size computation runs. */
+export function selectBusy(s: TaskState): boolean {
+  return Object.values(s.tasks).some(
+    (t) => !t.finished && t.kind !== "size_computation",
+  );
+}
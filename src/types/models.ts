@@ -7,15 +7,102 @@ export interface AppInfo {
   name: string;
   bundleId: string | null;
   version: string | null;
+  /** `CFBundleVersion` (build number). */
+  buildVersion: string | null;
   executable: string | null;
   /** Last-modified time as a Unix timestamp (seconds); `null` when unreadable. */
   modifiedAt: number | null;
+  /** Best-effort last-launch time as a Unix timestamp (seconds). */
+  lastUsedAt: number | null;
   running: boolean;
+  protection: AppProtection;
+  /** Slices of the main executable; empty when it isn't a Mach-O. */
+  architectures: Architecture[];
+  cask: string | null;
+  installSource: InstallSource;
 }
 
+export type Architecture =
+  | "x86_64"
+  | "arm64"
+  | "arm64e"
+  | "i386"
+  | "ppc"
+  | "other";
+
+/**
+ * `apple`: Apple-signed `com.apple.*` app, removable only with an override.
+ * `sealedSystem`: lives on the sealed system volume, never removable.
+ */
+export type AppProtection = "none" | "apple" | "sealedSystem";
+
+export type InstallSource =
+  | "appStore"
+  | "homebrew"
+  | "setapp"
+  | "pkg"
+  | "dragAndDrop"
+  | "unknown";
+
+export interface DuplicateEntry {
+  app: AppInfo;
+  newest: boolean;
+  /** Bundle size; only computed for redundant copies. */
+  size: number | null;
+}
+
+export interface DuplicateGroup {
+  /** Shared bundle id, or the normalized name when there is none. */
+  key: string;
+  apps: DuplicateEntry[];
+  redundantBytes: number;
+}
+
+export type SignerKind =
+  | "unknown"
+  | "adHoc"
+  | "apple"
+  | "appStore"
+  | "developerId"
+  | "development";
+
+export interface AppSecurityInfo {
+  signed: boolean;
+  signer: SignerKind;
+  identifier: string | null;
+  teamId: string | null;
+  /** Certificate common names, leaf first. */
+  authorities: string[];
+  flags: number;
+  hardenedRuntime: boolean;
+  entitlements: Record<string, unknown> | null;
+  sealedResources: number | null;
+  notarizationStapled: boolean;
+}
+
+export interface ThinnedFile {
+  path: string;
+  originalSize: number;
+  newSize: number;
+}
+
+export interface ThinReport {
+  appPath: string;
+  architecture: Architecture;
+  thinned: ThinnedFile[];
+  failed: UninstallFailure[];
+  bytesSaved: number;
+}
+
+/** Whether the filesystem failed or the removal policy refused the path. */
+export type FailureKind =
+  | { type: "error" }
+  | { type: "refused"; reason: string };
+
 export interface UninstallFailure {
   path: string;
   error: string;
+  kind: FailureKind;
 }
 
 export interface UninstallReport {
@@ -23,14 +110,210 @@ export interface UninstallReport {
   removed: string[];
   failed: UninstallFailure[];
   aborted: boolean;
+  /** Archive taken before removal, if backups are on. */
+  backup: string | null;
 }
 
-export type ProgressKind = "refresh_apps" | "find_related" | "uninstall";
+export interface UpdateCheck {
+  appPath: string;
+  name: string;
+  feedUrl: string;
+  installedVersion: string | null;
+  latestVersion: string | null;
+  latestBuild: string | null;
+  minimumSystemVersion: string | null;
+  downloadUrl: string | null;
+  releaseNotesUrl: string | null;
+  updateAvailable: boolean;
+  error: string | null;
+}
+
+export type ProgressKind =
+  | "refresh_apps"
+  | "find_related"
+  | "uninstall"
+  | "check_updates"
+  | "size_computation"
+  | "storage_analysis"
+  | "clean_data"
+  | "reset_app";
 
 export interface ProgressEvent {
+  taskId: number;
+  /** Per-task sequence number, starting at 0. */
+  seq: number;
   kind: ProgressKind;
   progress: number;
   message: string;
   finished: boolean;
   error: string | null;
+  done: number | null;
+  total: number | null;
+  bytes: number | null;
+  elapsedMs: number;
+  etaMs: number | null;
+}
+
+export type ExportFormat = "json" | "csv" | "markdown";
+
+export interface InventoryItem {
+  path: string;
+  size: number | null;
+}
+
+export interface InventoryEntry {
+  name: string;
+  bundleId: string | null;
+  version: string | null;
+  path: string;
+  size: number | null;
+  running: boolean;
+  installSource: InstallSource;
+  related: InventoryItem[] | null;
+}
+
+export interface ProfileAppResult {
+  appPath: string;
+  name: string;
+  related: string[];
+  kept: string[];
+  killed: number;
+  report: UninstallReport | null;
+  error: string | null;
+}
+
+export interface ProfileReport {
+  profileName: string | null;
+  dryRun: boolean;
+  apps: ProfileAppResult[];
+  unmatched: string[];
+  extraRemoved: string[];
+  extraFailed: UninstallFailure[];
+}
+
+export interface ExclusionRules {
+  paths: string[];
+  globs: string[];
+  bundleIds: string[];
+}
+
+export type ErrorCode =
+  | "permissionDenied"
+  | "notFound"
+  | "appRunning"
+  | "protected"
+  | "trashUnavailable"
+  | "cancelled"
+  | "io";
+
+/** Rejection value of every Tauri command. */
+export interface CommandError {
+  code: ErrorCode;
+  message: string;
+  path: string | null;
+}
+
+export type RemovalMode = "trash" | "trashOrDelete" | "delete";
+
+export type KillPolicy = "refuse" | "kill";
+
+export interface Settings {
+  schemaVersion: number;
+  scanRoots: string[];
+  removalMode: RemovalMode;
+  killPolicy: KillPolicy;
+  killDeadlineMs: number;
+  relatedMaxDepth: number;
+  transactionalUninstall: boolean;
+  backupBeforeUninstall: boolean;
+  /** Empty means the app data folder's `backups`. */
+  backupsDir: string;
+  backupFormat: BackupFormat;
+}
+
+export type BackupFormat = "tarZst" | "zip";
+
+export type FullDiskAccess = "granted" | "missing" | "unknown";
+
+export interface LocationAccess {
+  path: string;
+  exists: boolean;
+  readable: boolean;
+  writable: boolean;
+  error: string | null;
+}
+
+export interface AccessReport {
+  fullDiskAccess: FullDiskAccess;
+  locations: LocationAccess[];
+}
+
+export interface StorageEntry {
+  path: string;
+  /** Library folder the entry sits in, e.g. `Caches`. */
+  category: string;
+  bytes: number;
+}
+
+export interface AppStorage {
+  appPath: string;
+  name: string;
+  bundleId: string | null;
+  bytes: number;
+  entries: StorageEntry[];
+}
+
+export interface StorageReport {
+  totalBytes: number;
+  apps: AppStorage[];
+  unattributed: StorageEntry[];
+}
+
+export type CleanCategory =
+  | "caches"
+  | "logs"
+  | "httpStorages"
+  | "savedApplicationState"
+  | "diagnosticReports";
+
+export interface CleanItem {
+  path: string;
+  category: CleanCategory;
+  bytes: number;
+}
+
+/** Result of `clean_app_data`, shaped like `UninstallReport`. */
+export interface CleanReport {
+  appPath: string;
+  removed: string[];
+  failed: UninstallFailure[];
+  freedBytes: number;
+}
+
+/** Result of `reset_app`: an `UninstallReport` plus kill count. */
+export interface ResetReport extends UninstallReport {
+  killed: number;
+}
+
+/** Result of `restore_backup`. Entries whose location is taken again are
+ * in `failed` as refused. */
+export interface RestoreReport {
+  archive: string;
+  restored: string[];
+  failed: UninstallFailure[];
+}
+
+/** One rename `migrate_app_data` performs; `blocked` says why it won't. */
+export interface MigrationMove {
+  from: string;
+  to: string;
+  blocked: string | null;
+}
+
+/** Result of `migrate_app_data`. Blocked moves are in `failed` as refused. */
+export interface MigrationReport {
+  fromBundleId: string;
+  toBundleId: string;
+  moved: MigrationMove[];
+  failed: UninstallFailure[];
 }